@@ -0,0 +1,202 @@
+use crate::maths::mat::Mat4x4;
+use crate::maths::vec::Vec3d;
+
+/// Maximum pitch, just short of straight up/down so the view never flips
+const MAX_PITCH: f32 = std::f32::consts::FRAC_PI_2 - 0.01;
+
+/// Closest an orbit camera may zoom to its target
+const MIN_DISTANCE: f32 = 0.1;
+
+/// Get unit vector looking in the direction given by `yaw` and `pitch`, where zero looks along +z
+fn direction(yaw: f32, pitch: f32) -> Vec3d {
+    Vec3d::new(pitch.cos() * yaw.sin(), pitch.sin(), pitch.cos() * yaw.cos())
+}
+
+/// Get unit vector pointing to the right of `yaw`, parallel to the ground
+fn right(yaw: f32) -> Vec3d {
+    Vec3d::new(yaw.cos(), 0.0, -yaw.sin())
+}
+
+/// Anything which can be looked through to view a scene
+pub trait Viewpoint {
+    /// Get the position of the eye, in world space
+    fn position(&self) -> Vec3d;
+
+    /// Get the view matrix, which transforms world space into camera space
+    fn view_matrix(&self) -> Mat4x4;
+}
+
+/// First-person camera, looking along +z when yaw and pitch are zero
+pub struct Camera {
+    pub position: Vec3d,
+    /// Rotation around the y axis, in radians
+    pub yaw: f32,
+    /// Rotation up/down from the horizon, in radians
+    pub pitch: f32,
+}
+
+impl Camera {
+    /// Construct a camera at `position`, looking along +z
+    pub fn new(position: Vec3d) -> Camera {
+        Camera {
+            position,
+            yaw: 0.0,
+            pitch: 0.0,
+        }
+    }
+
+    /// Get unit vector in the direction the camera is looking
+    pub fn forward(&self) -> Vec3d {
+        direction(self.yaw, self.pitch)
+    }
+
+    /// Get unit vector pointing to the camera's right, parallel to the ground
+    pub fn right(&self) -> Vec3d {
+        right(self.yaw)
+    }
+
+    /// Move along the look direction
+    pub fn move_forward(&mut self, d: f32) {
+        self.position += self.forward() * d;
+    }
+
+    /// Move sideways. Positive `d` moves right
+    pub fn strafe(&mut self, d: f32) {
+        self.position += self.right() * d;
+    }
+
+    /// Move straight up/down
+    pub fn rise(&mut self, d: f32) {
+        self.position.y += d;
+    }
+
+    /// Rotate the camera. Pitch is clamped so the camera cannot look past vertical
+    pub fn turn(&mut self, dyaw: f32, dpitch: f32) {
+        self.yaw += dyaw;
+        self.pitch = (self.pitch + dpitch).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+}
+
+impl Viewpoint for Camera {
+    fn position(&self) -> Vec3d {
+        self.position
+    }
+
+    fn view_matrix(&self) -> Mat4x4 {
+        let target = self.position + self.forward();
+        Mat4x4::point_at(&self.position, &target, &Vec3d::unit_y()).quick_inverse()
+    }
+}
+
+/// Turntable camera, which circles around and looks at a target point
+pub struct OrbitCamera {
+    pub target: Vec3d,
+    /// Distance from the target
+    pub distance: f32,
+    /// Rotation around the target's y axis, in radians
+    pub yaw: f32,
+    /// Elevation of the look direction, in radians. Positive values look upwards at the target
+    pub pitch: f32,
+}
+
+impl OrbitCamera {
+    /// Construct a camera looking at `target` along +z from `distance` away
+    pub fn new(target: Vec3d, distance: f32) -> OrbitCamera {
+        OrbitCamera {
+            target,
+            distance,
+            yaw: 0.0,
+            pitch: 0.0,
+        }
+    }
+
+    /// Get unit vector in the direction the camera is looking
+    pub fn forward(&self) -> Vec3d {
+        direction(self.yaw, self.pitch)
+    }
+
+    /// Circle around the target. Pitch is clamped so the camera cannot pass over the top or bottom
+    pub fn orbit(&mut self, dyaw: f32, dpitch: f32) {
+        self.yaw += dyaw;
+        self.pitch = (self.pitch + dpitch).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// Multiply distance from the target by `factor`, so values below 1 zoom in
+    pub fn zoom(&mut self, factor: f32) {
+        self.distance = (self.distance * factor).max(MIN_DISTANCE);
+    }
+
+    /// Move the target (and the camera with it) across the view plane
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        let right = right(self.yaw);
+        let up = Vec3d::normal(&self.forward(), &right);
+        self.target += right * dx + up * dy;
+    }
+
+    /// Look at the centre of the box `min`-`max`, far enough away that the whole box is visible with a field of view of `fov` radians
+    pub fn fit(&mut self, min: &Vec3d, max: &Vec3d, fov: f32) {
+        let radius = (*max - *min).length() * 0.5;
+        self.target = (*min + *max) * 0.5;
+        self.distance = (radius / (fov * 0.5).sin()).max(MIN_DISTANCE);
+    }
+}
+
+impl Viewpoint for OrbitCamera {
+    fn position(&self) -> Vec3d {
+        self.target - self.forward() * self.distance
+    }
+
+    fn view_matrix(&self) -> Mat4x4 {
+        Mat4x4::point_at(&self.position(), &self.target, &Vec3d::unit_y()).quick_inverse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::assert_vec;
+
+    #[test]
+    fn fit() {
+        // The box's half diagonal is sqrt(3), which must fill half of a 90 degree field of view
+        let mut camera = OrbitCamera::new(Vec3d::origin(), 1.0);
+        camera.fit(&Vec3d::new(1.0, 2.0, 3.0), &Vec3d::new(3.0, 4.0, 5.0), std::f32::consts::FRAC_PI_2);
+        assert_vec(&camera.target, 2.0, 3.0, 4.0);
+        assert!((camera.distance - 6.0f32.sqrt()).abs() < 1e-5, "{}", camera.distance);
+
+        // A single point is seen from the closest allowed distance
+        camera.fit(&Vec3d::origin(), &Vec3d::origin(), std::f32::consts::FRAC_PI_2);
+        assert_eq!(camera.distance, MIN_DISTANCE);
+    }
+
+    #[test]
+    fn pitch_clamped() {
+        let mut orbit = OrbitCamera::new(Vec3d::origin(), 5.0);
+        orbit.orbit(0.5, 10.0);
+        assert_eq!((orbit.yaw, orbit.pitch), (0.5, MAX_PITCH));
+        orbit.orbit(0.0, -20.0);
+        assert_eq!(orbit.pitch, -MAX_PITCH);
+
+        let mut camera = Camera::new(Vec3d::origin());
+        camera.turn(0.0, -10.0);
+        assert_eq!(camera.pitch, -MAX_PITCH);
+        camera.turn(0.0, 0.25 - camera.pitch);
+        assert!((camera.pitch - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn view_matrix_centres_target() {
+        let mut orbit = OrbitCamera::new(Vec3d::new(1.0, 2.0, 3.0), 4.0);
+        orbit.orbit(0.7, -0.4);
+        let view = orbit.view_matrix();
+        // The target lies straight ahead, and the camera at the origin of view space
+        assert_vec(&(orbit.target * view), 0.0, 0.0, 4.0);
+        assert_vec(&(orbit.position() * view), 0.0, 0.0, 0.0);
+        // Up in the world stays up in view space
+        assert!(((orbit.target + Vec3d::unit_y()) * view).y > 0.0);
+
+        let mut camera = Camera::new(Vec3d::new(-1.0, 0.5, 2.0));
+        camera.turn(-1.2, 0.3);
+        assert_vec(&((camera.position + camera.forward() * 2.0) * camera.view_matrix()), 0.0, 0.0, 2.0);
+    }
+}
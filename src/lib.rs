@@ -0,0 +1,23 @@
+//! A software 3D renderer: vector maths, meshes and the files they are loaded from, and a pipeline which takes a `Scene`,
+//! seen through a camera, to pixels in a `Framebuffer`. Nothing here needs a window; the `window` feature adds the viewer binary
+
+pub mod camera;
+pub mod lighting;
+pub mod maths;
+pub mod mesh;
+pub mod pipeline;
+pub mod raster;
+pub mod scene;
+pub mod texture;
+#[cfg(test)]
+mod test_util;
+pub mod triangle;
+
+pub use camera::{Camera, OrbitCamera, Viewpoint};
+pub use maths::mat::Mat4x4;
+pub use maths::quat::Quat;
+pub use maths::vec::{Vec3d, Vec4d};
+pub use mesh::{shape, Mesh, Shading};
+pub use pipeline::Pipeline;
+pub use raster::Framebuffer;
+pub use scene::{Node, Scene, Transform};
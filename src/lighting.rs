@@ -0,0 +1,374 @@
+use std::ops::{Add, Mul};
+
+use colorsys::Rgb;
+
+use crate::maths::vec::Vec3d;
+
+/// Default strength of the ambient light, which lights every face regardless of its direction
+pub const AMBIENT: f32 = 0.1;
+
+/// Convert a channel of an sRGB color, from 0 to 1, to linear RGB
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Convert a channel of a linear RGB color, from 0 to 1, to sRGB for display
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Amount of red, green and blue light falling on a point. 1 is the full strength of a white light, and may be exceeded
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Intensity {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+impl Intensity {
+    pub fn new(r: f32, g: f32, b: f32) -> Intensity {
+        Intensity { r, g, b }
+    }
+
+    /// White light of the given strength
+    pub fn grey(k: f32) -> Intensity {
+        Intensity::new(k, k, k)
+    }
+
+    /// Light of the given sRGB color (each channel 0 to 255) and strength
+    pub fn from_rgb(rgb: &Rgb, k: f32) -> Intensity {
+        let channel = |c: f64| srgb_to_linear(c as f32 / 255.0) * k;
+        Intensity::new(channel(rgb.red()), channel(rgb.green()), channel(rgb.blue()))
+    }
+
+    /// Get the strength of the brightest channel
+    pub fn max(&self) -> f32 {
+        self.r.max(self.g).max(self.b)
+    }
+}
+
+impl Add for Intensity {
+    type Output = Intensity;
+
+    fn add(self, rhs: Intensity) -> Intensity {
+        Intensity::new(self.r + rhs.r, self.g + rhs.g, self.b + rhs.b)
+    }
+}
+
+impl Mul<f32> for Intensity {
+    type Output = Intensity;
+
+    fn mul(self, k: f32) -> Intensity {
+        Intensity::new(self.r * k, self.g * k, self.b * k)
+    }
+}
+
+/// Multiply each channel by the same channel of the other intensity
+impl Mul for Intensity {
+    type Output = Intensity;
+
+    fn mul(self, rhs: Intensity) -> Intensity {
+        Intensity::new(self.r * rhs.r, self.g * rhs.g, self.b * rhs.b)
+    }
+}
+
+/// Light reaching the eye from a point on a surface. `diffuse` (which includes ambient light) is tinted by the surface's color,
+/// and `specular` is added on top, so highlights keep the color of the light and of the material's specular reflectance
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Illumination {
+    pub diffuse: Intensity,
+    pub specular: Intensity,
+}
+
+impl Illumination {
+    pub fn new(diffuse: Intensity, specular: Intensity) -> Illumination {
+        Illumination { diffuse, specular }
+    }
+}
+
+impl Add for Illumination {
+    type Output = Illumination;
+
+    fn add(self, rhs: Illumination) -> Illumination {
+        Illumination::new(self.diffuse + rhs.diffuse, self.specular + rhs.specular)
+    }
+}
+
+impl Mul<f32> for Illumination {
+    type Output = Illumination;
+
+    fn mul(self, k: f32) -> Illumination {
+        Illumination::new(self.diffuse * k, self.specular * k)
+    }
+}
+
+/// Diffuse light only, with no highlight
+impl From<Intensity> for Illumination {
+    fn from(diffuse: Intensity) -> Self {
+        Illumination::new(diffuse, Intensity::grey(0.0))
+    }
+}
+
+/// A source of light. Colors are 0 to 255 in each channel, and scaled by `intensity`
+#[derive(Clone)]
+pub enum Light {
+    /// Light from far away, travelling in `direction` everywhere
+    Directional { direction: Vec3d, color: Rgb, intensity: f32 },
+    /// Light shining in every direction from `position`. Its strength is divided by `constant + linear * d + quadratic * d^2` at distance `d`
+    Point { position: Vec3d, color: Rgb, intensity: f32, attenuation: (f32, f32, f32) },
+    /// Light shining from `position` in a cone around `direction`. It has full strength within `inner` radians of `direction`,
+    /// fading to nothing at `outer` radians. Attenuated as a point light
+    Spot { position: Vec3d, direction: Vec3d, color: Rgb, intensity: f32, attenuation: (f32, f32, f32), inner: f32, outer: f32 },
+}
+
+impl Light {
+    /// White directional light at full strength, travelling in `direction`
+    pub fn directional(direction: &Vec3d) -> Light {
+        Light::Directional { direction: *direction, color: Rgb::new(255.0, 255.0, 255.0, None), intensity: 1.0 }
+    }
+
+    /// Get the unit direction from `point` towards the light, and the light reaching `point`. Return None if no light reaches it,
+    /// or if the direction is undefined because `point` is at the light or a spot light has no direction
+    fn incident(&self, point: &Vec3d) -> Option<(Vec3d, Intensity)> {
+        let attenuate = |d: f32, (constant, linear, quadratic): (f32, f32, f32)| 1.0 / (constant + linear * d + quadratic * d * d).max(f32::EPSILON);
+        match self {
+            Light::Directional { direction, color, intensity } => {
+                Some(((-*direction).normalise(), Intensity::from_rgb(color, *intensity)))
+            }
+            Light::Point { position, color, intensity, attenuation } => {
+                let to_light = *position - *point;
+                let d = to_light.length();
+                if d <= f32::EPSILON {
+                    return None;
+                }
+                Some((to_light * (1.0 / d), Intensity::from_rgb(color, intensity * attenuate(d, *attenuation))))
+            }
+            Light::Spot { position, direction, color, intensity, attenuation, inner, outer } => {
+                let to_light = *position - *point;
+                let d = to_light.length();
+                let length = direction.length();
+                if d <= f32::EPSILON || length <= f32::EPSILON {
+                    return None;
+                }
+                let to_light = to_light * (1.0 / d);
+
+                // Fade smoothly between the inner and outer cones
+                let cos = -Vec3d::dot_product(&to_light, &(*direction * (1.0 / length)));
+                let (cos_inner, cos_outer) = (inner.cos(), outer.cos());
+                let t = ((cos - cos_outer) / (cos_inner - cos_outer).max(f32::EPSILON)).clamp(0.0, 1.0);
+                let cone = t * t * (3.0 - 2.0 * t);
+                if cone <= 0.0 {
+                    return None;
+                }
+                Some((to_light, Intensity::from_rgb(color, intensity * attenuate(d, *attenuation) * cone)))
+            }
+        }
+    }
+}
+
+/// How specular highlights are calculated
+#[derive(Clone, Copy, PartialEq)]
+pub enum Reflection {
+    /// Compare the view direction against the light reflected in the surface
+    Phong,
+    /// Compare the normal against the direction halfway between the light and the viewer. Highlights are larger than Phong's for the same shininess
+    BlinnPhong,
+}
+
+/// How a surface responds to light
+#[derive(Clone, Copy)]
+pub struct Material {
+    /// Specular reflectance of each channel in linear RGB, from 0 (no highlight) to 1
+    pub specular: Intensity,
+    /// Specular exponent. Higher values give smaller, sharper highlights
+    pub shininess: f32,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Material { specular: Intensity::grey(0.0), shininess: 1.0 }
+    }
+}
+
+/// Every light in a scene, with ambient, diffuse and specular terms
+pub struct Lighting {
+    pub lights: Vec<Light>,
+    /// Position of the viewer in world space, for specular highlights
+    pub eye: Vec3d,
+    /// Light reaching every point, whatever its direction
+    pub ambient: Intensity,
+    pub reflection: Reflection,
+}
+
+impl Lighting {
+    /// Construct lighting from `lights`, seen from `eye`
+    pub fn new(lights: Vec<Light>, eye: &Vec3d) -> Lighting {
+        Lighting {
+            lights,
+            eye: *eye,
+            ambient: Intensity::grey(AMBIENT),
+            reflection: Reflection::BlinnPhong,
+        }
+    }
+
+    /// Get the light reaching the eye from a point on a surface with the given unit normal, both in world space, summed over every light
+    pub fn light(&self, material: &Material, normal: &Vec3d, position: &Vec3d) -> Illumination {
+        let view = (self.eye - *position).normalise();
+        let mut total = Illumination::from(self.ambient);
+        for light in &self.lights {
+            let Some((to_light, incident)) = light.incident(position) else { continue };
+            let diffuse = Vec3d::dot_product(normal, &to_light);
+            if diffuse <= 0.0 {
+                continue;
+            }
+            total.diffuse = total.diffuse + incident * diffuse;
+
+            if material.specular.max() > 0.0 {
+                let alignment = match self.reflection {
+                    Reflection::Phong => {
+                        let reflected = *normal * (2.0 * diffuse) - to_light;
+                        Vec3d::dot_product(&reflected, &view)
+                    }
+                    Reflection::BlinnPhong => Vec3d::dot_product(normal, &(to_light + view).normalise()),
+                };
+                let highlight = alignment.max(0.0).powf(material.shininess.max(1.0));
+                total.specular = total.specular + incident * material.specular * highlight;
+            }
+        }
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_light(light: Intensity, expected: Intensity) {
+        assert!((light.r - expected.r).abs() < 1e-5 && (light.g - expected.g).abs() < 1e-5 && (light.b - expected.b).abs() < 1e-5, "{:?} != {:?}", light, expected);
+    }
+
+    fn assert_grey(light: Intensity, k: f32) {
+        assert_light(light, Intensity::grey(k));
+    }
+
+    /// Get the diffuse light reaching a point, checking there is no highlight
+    fn diffuse(light: Illumination) -> Intensity {
+        assert_eq!(light.specular, Intensity::grey(0.0));
+        light.diffuse
+    }
+
+    #[test]
+    fn srgb_round_trip() {
+        assert_eq!(srgb_to_linear(0.0), 0.0);
+        assert!((srgb_to_linear(1.0) - 1.0).abs() < 1e-6);
+        assert!((srgb_to_linear(0.5) - 0.214041).abs() < 1e-5);
+        assert!((linear_to_srgb(0.214041) - 0.5).abs() < 1e-5);
+        for i in 0..=255 {
+            let c = i as f32 / 255.0;
+            assert!((linear_to_srgb(srgb_to_linear(c)) - c).abs() < 1e-5, "{}", i);
+        }
+    }
+
+    #[test]
+    fn diffuse_and_ambient() {
+        let lighting = Lighting::new(vec![Light::directional(&Vec3d::new(0.0, 0.0, 2.0))], &Vec3d::new(0.0, 0.0, -5.0));
+        let material = Material::default();
+        assert_grey(diffuse(lighting.light(&material, &Vec3d::new(0.0, 0.0, -1.0), &Vec3d::origin())), 1.1);
+        assert_grey(diffuse(lighting.light(&material, &Vec3d::new(0.0, 0.6, -0.8), &Vec3d::origin())), 0.9);
+        assert_grey(diffuse(lighting.light(&material, &Vec3d::unit_z(), &Vec3d::origin())), 0.1);
+    }
+
+    #[test]
+    fn specular() {
+        // Light from above, viewer to one side: the mirror direction points straight at the viewer
+        let mut lighting = Lighting::new(vec![Light::directional(&Vec3d::new(1.0, -1.0, 0.0))], &Vec3d::new(1.0, 1.0, 0.0));
+        let material = Material { specular: Intensity::new(0.5, 0.25, 0.0), shininess: 16.0 };
+        let normal = Vec3d::unit_y();
+        let diffuse = 0.1 + std::f32::consts::FRAC_1_SQRT_2;
+        for reflection in [Reflection::Phong, Reflection::BlinnPhong] {
+            lighting.reflection = reflection;
+            // The highlight is kept apart from the diffuse light, in the color of the specular reflectance
+            let light = lighting.light(&material, &normal, &Vec3d::origin());
+            assert_grey(light.diffuse, diffuse);
+            assert_light(light.specular, Intensity::new(0.5, 0.25, 0.0));
+            // Away from the mirror direction the highlight falls off
+            let off = lighting.light(&material, &normal, &Vec3d::new(0.5, 0.0, 0.0));
+            assert_grey(off.diffuse, diffuse);
+            assert!(off.specular.r > 0.0 && off.specular.r < 0.5);
+        }
+    }
+
+    #[test]
+    fn colored_lights_add() {
+        let red = Light::Directional { direction: Vec3d::new(0.0, -1.0, 0.0), color: Rgb::new(255.0, 0.0, 0.0, None), intensity: 0.5 };
+        let blue = Light::Directional { direction: Vec3d::new(0.0, -1.0, 0.0), color: Rgb::new(0.0, 0.0, 255.0, None), intensity: 2.0 };
+        let lighting = Lighting::new(vec![red, blue], &Vec3d::new(0.0, 5.0, 0.0));
+        let light = lighting.light(&Material::default(), &Vec3d::unit_y(), &Vec3d::origin());
+        assert_light(diffuse(light), Intensity::new(0.6, 0.1, 2.1));
+    }
+
+    #[test]
+    fn point_attenuation() {
+        let point = Light::Point { position: Vec3d::new(0.0, 2.0, 0.0), color: Rgb::new(255.0, 255.0, 255.0, None), intensity: 1.0, attenuation: (1.0, 0.0, 0.25) };
+        let mut lighting = Lighting::new(vec![point], &Vec3d::new(0.0, 5.0, 0.0));
+        lighting.ambient = Intensity::grey(0.0);
+        // Straight below at distance 2: 1 / (1 + 0.25 * 4)
+        assert_grey(diffuse(lighting.light(&Material::default(), &Vec3d::unit_y(), &Vec3d::origin())), 0.5);
+    }
+
+    #[test]
+    fn spot_cone() {
+        let spot = Light::Spot {
+            position: Vec3d::new(0.0, 1.0, 0.0),
+            direction: Vec3d::new(0.0, -1.0, 0.0),
+            color: Rgb::new(255.0, 255.0, 255.0, None),
+            intensity: 1.0,
+            attenuation: (1.0, 0.0, 0.0),
+            inner: 20.0f32.to_radians(),
+            outer: 40.0f32.to_radians(),
+        };
+        let mut lighting = Lighting::new(vec![spot], &Vec3d::new(0.0, 5.0, 0.0));
+        lighting.ambient = Intensity::grey(0.0);
+        let at = |x: f32| lighting.light(&Material::default(), &Vec3d::unit_y(), &Vec3d::new(x, 0.0, 0.0)).diffuse.r;
+
+        // Inside the inner cone, only the angle of incidence matters
+        assert!((at(0.0) - 1.0).abs() < 1e-5);
+        assert!((at(0.2) - 0.2f32.atan().cos()).abs() < 1e-5);
+        // Between the cones the light fades, and outside it there is none
+        assert!(at(30.0f32.to_radians().tan()) > 0.0 && at(30.0f32.to_radians().tan()) < 30.0f32.to_radians().cos());
+        assert_eq!(at(1.0), 0.0);
+    }
+
+    #[test]
+    fn point_at_surface() {
+        let point = Light::Point { position: Vec3d::origin(), color: Rgb::new(255.0, 255.0, 255.0, None), intensity: 1.0, attenuation: (1.0, 0.0, 0.0) };
+        assert!(point.incident(&Vec3d::origin()).is_none());
+        let lighting = Lighting::new(vec![point], &Vec3d::new(0.0, 5.0, 0.0));
+        assert_grey(diffuse(lighting.light(&Material::default(), &Vec3d::unit_y(), &Vec3d::origin())), 0.1);
+    }
+
+    #[test]
+    fn spot_without_direction() {
+        let spot = |position: Vec3d, direction: Vec3d| Light::Spot {
+            position,
+            direction,
+            color: Rgb::new(255.0, 255.0, 255.0, None),
+            intensity: 1.0,
+            attenuation: (1.0, 0.0, 0.0),
+            inner: 20.0f32.to_radians(),
+            outer: 40.0f32.to_radians(),
+        };
+        assert!(spot(Vec3d::new(0.0, 1.0, 0.0), Vec3d::origin()).incident(&Vec3d::origin()).is_none());
+        assert!(spot(Vec3d::origin(), Vec3d::new(0.0, -1.0, 0.0)).incident(&Vec3d::origin()).is_none());
+        let lighting = Lighting::new(vec![spot(Vec3d::new(0.0, 1.0, 0.0), Vec3d::origin())], &Vec3d::new(0.0, 5.0, 0.0));
+        assert_grey(diffuse(lighting.light(&Material::default(), &Vec3d::unit_y(), &Vec3d::origin())), 0.1);
+    }
+}
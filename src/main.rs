@@ -1,176 +1,223 @@
-use ggez::conf::{WindowMode, WindowSetup};
-use ggez::event::{self, EventHandler};
-use ggez::graphics::{self, Color};
-use ggez::{Context, ContextBuilder, GameResult};
-use ggez::glam::*;
-
-mod util;
-pub use util::mesh::{Mesh, shape};
-pub use util::maths::mat::Mat4x4;
-pub use util::maths::vec::Vec3d;
-use util::triangle::Triangle;
+use std::path::PathBuf;
 
+use threed_renderer::lighting::Light;
+use threed_renderer::texture::Filter;
+use threed_renderer::{mesh, pipeline, Mat4x4, OrbitCamera, Pipeline, Scene, Shading, Vec3d};
+
+#[cfg(feature = "window")]
+mod viewer;
+
+/// Width of the window, and of frames rendered to a file unless `--size` is given
 const WIN_WIDTH: f32 = 1090.0;
+/// Height of the window, and of frames rendered to a file unless `--size` is given
 const WIN_HEIGHT: f32 = 720.0;
 
+/// Model shown when none is given on the command line
+const DEFAULT_MODEL: &str = "models/spaceship.obj";
+
+/// Usage: `threed_renderer [model.obj|model.stl|model.ply|model.gltf|model.glb] [--export <output.obj|output.stl> [--ascii]] [--render <output.png|output.ppm>] [--size <width>x<height>] [--yaw <degrees>] [--pitch <degrees>] [--shading <flat|smooth|pixel>] [--light <light>]... [--filter <nearest|bilinear>] [--orthographic]`
+///
+/// With `--export`, the model is written to the given file (as STL, binary unless `--ascii` is given, or OBJ with its materials alongside).
+/// With `--render`, a single frame is rendered without opening a window and written to the given file, shaded as given by `--shading` (flat by default)
 fn main() {
-    // Make a Context.
-    let cb = ContextBuilder::new("3D Renderer", "triflicAcid")
-        .window_setup(WindowSetup::default().title("3D Renderer"))
-        .window_mode(WindowMode::default().dimensions(WIN_WIDTH, WIN_HEIGHT));
-    let (mut ctx, event_loop) = cb.build().expect("Could not create ggez context!");
-    let mut rd = Renderer::new(&mut ctx);
-    
-
-    let style = util::mesh::FaceSettings {
-        fill: Some(colorsys::Rgb::new(255.0, 255.0, 128.0, None)),
-        // stroke: Some(colorsys::Rgb::new(255.0, 0.0, 0.0, None)),
-        stroke: None,
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match Options::parse(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
     };
 
-    let res = util::mesh::obj::parse_file("models/spaceship.obj".to_string());
-    if res.is_err() {
-        println!("{}", res.unwrap_err());
-    } else {
-        let mut mesh = res.unwrap();
+    let model = load_model(&options.model);
+    if let Err(e) = &model {
+        eprintln!("{}", e);
+    }
 
-        mesh.add_style(&style);
-        mesh.set_global_style(Some(0));
-        rd.mesh.merge(&mut mesh);
+    if let Some(export) = &options.export {
+        // Export formats have no hierarchy, so every instance is written out in world space
+        let scene = model.as_ref().unwrap_or_else(|_| std::process::exit(1));
+        if let Err(e) = mesh::save_file(&scene.flatten(), export, options.ascii) {
+            eprintln!("Could not write {}: {}", export.display(), e);
+            std::process::exit(1);
+        }
+        if options.output.is_none() {
+            return;
+        }
     }
 
+    if let Some(output) = &options.output {
+        let mut scene = model.unwrap_or_else(|_| std::process::exit(1));
+        let mut camera = OrbitCamera::new(Vec3d::origin(), 5.0);
+        if let Some((min, max)) = scene.bounds() {
+            camera.fit(&min, &max, pipeline::FOV.to_radians());
+        }
+        camera.orbit(options.yaw.to_radians(), options.pitch.to_radians());
+
+        let lights = if options.lights.is_empty() { vec![Light::directional(&Vec3d::unit_z())] } else { options.lights };
+        let mut pipeline = Pipeline::new(Mat4x4::identity(), &camera, lights, options.width as f32, options.height as f32);
+        pipeline.shading = options.shading;
+        pipeline.filter = options.filter;
+        if options.orthographic {
+            pipeline.proj = pipeline::orthographic(camera.distance, options.width as f32, options.height as f32);
+        }
+        let frame = pipeline.render_scene(&mut scene);
+        if let Err(e) = frame.save(output) {
+            eprintln!("Could not write {}: {}", output.display(), e);
+            std::process::exit(1);
+        }
+        return;
+    }
 
-    event::run(ctx, event_loop, rd);
+    #[cfg(feature = "window")]
+    viewer::run(model.ok(), options.lights, options.filter, options.orthographic);
+    #[cfg(not(feature = "window"))]
+    {
+        eprintln!("Built without the window feature, so can only --export or --render");
+        std::process::exit(2);
+    }
 }
 
-struct Renderer {
-    mesh: Mesh,
-    proj: Mat4x4,
-    theta: f32,
-    light_dir: Vec3d,
-    camera: Vec3d,
+/// Command line options
+struct Options {
+    model: String,
+    /// Write the model to this file instead of opening a window
+    export: Option<PathBuf>,
+    /// Export STL files as ASCII rather than binary
+    ascii: bool,
+    /// Render a single frame to this file instead of opening a window
+    output: Option<PathBuf>,
+    width: usize,
+    height: usize,
+    /// Orbit camera yaw, in degrees
+    yaw: f32,
+    /// Orbit camera pitch, in degrees
+    pitch: f32,
+    /// Shading used when rendering to a file
+    shading: Shading,
+    /// Lights fixed in the world. If none are given, a single white light shines from the initial camera position
+    lights: Vec<Light>,
+    /// How textures are sampled
+    filter: Filter,
+    /// Use an orthographic projection rather than perspective
+    orthographic: bool,
 }
 
-impl Renderer {
-    pub fn new(_ctx: &mut Context) -> Renderer {
-        let near = 1.0;
-        let far = 1000.0;
-        let fov = 90.0;
-        let fov_rad = 1.0 / (fov * 0.5 / 180.0 * std::f32::consts::PI).tan();
-        let ar = WIN_HEIGHT / WIN_WIDTH;
-        Renderer {
-            mesh: Mesh::new(),
-            proj: Mat4x4(
-                (ar * fov_rad, 0.0, 0.0, 0.0),
-                (0.0, fov_rad, 0.0, 0.0),
-                (0.0, 0.0, far / (far - near), 1.0),
-                (0.0, 0.0, (-far * near) / (far - near), 0.0),
-            ),
-            light_dir: Vec3d::new(0.0, 0.0, -1.0),
-            camera: Vec3d::new(0.0, 0.0, 0.0),
-            theta: 0.0,
+impl Options {
+    /// Parse command line arguments (excluding the program name)
+    fn parse(args: &[String]) -> Result<Options, String> {
+        let mut options = Options {
+            model: DEFAULT_MODEL.to_string(),
+            export: None,
+            ascii: false,
+            output: None,
+            width: WIN_WIDTH as usize,
+            height: WIN_HEIGHT as usize,
+            yaw: 0.0,
+            pitch: 0.0,
+            shading: Shading::Flat,
+            lights: Vec::new(),
+            filter: Filter::Bilinear,
+            orthographic: false,
+        };
+
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let mut value = || iter.next().ok_or(format!("Missing value for {}", arg));
+            match arg.as_str() {
+                "--export" => options.export = Some(PathBuf::from(value()?)),
+                "--ascii" => options.ascii = true,
+                "--render" => options.output = Some(PathBuf::from(value()?)),
+                "--shading" => {
+                    options.shading = match value()?.as_str() {
+                        "flat" => Shading::Flat,
+                        "smooth" => Shading::Smooth,
+                        "pixel" => Shading::PerPixel,
+                        other => return Err(format!("Invalid shading {}, expected flat, smooth or pixel", other)),
+                    }
+                }
+                "--size" => {
+                    let size = value()?;
+                    let (w, h) = size.split_once('x').ok_or(format!("Invalid size {}, expected <width>x<height>", size))?;
+                    options.width = w.parse().map_err(|_| format!("Invalid width {}", w))?;
+                    options.height = h.parse().map_err(|_| format!("Invalid height {}", h))?;
+                }
+                "--filter" => {
+                    options.filter = match value()?.as_str() {
+                        "nearest" => Filter::Nearest,
+                        "bilinear" => Filter::Bilinear,
+                        other => return Err(format!("Invalid filter {}, expected nearest or bilinear", other)),
+                    }
+                }
+                "--orthographic" => options.orthographic = true,
+                "--light" => options.lights.push(parse_light(value()?)?),
+                "--yaw" => options.yaw = value()?.parse().map_err(|_| "Invalid yaw".to_string())?,
+                "--pitch" => options.pitch = value()?.parse().map_err(|_| "Invalid pitch".to_string())?,
+                _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+                _ => options.model = arg.clone(),
+            }
         }
-    }
-}
 
-impl EventHandler for Renderer {
-    fn update(&mut self, _ctx: &mut Context) -> GameResult {
-        self.theta = self.theta + 0.02; // % (2.0 * std::f32::consts::PI);
-        Ok(())
+        Ok(options)
     }
+}
 
-    fn draw(&mut self, ctx: &mut Context) -> GameResult {
-        let rot_x = Mat4x4::rot_x(self.theta * 0.5);
-        let rot_z = Mat4x4::rot_z(self.theta);
-        let rot = Mat4x4::mult(&rot_z, &rot_x);
-        let scale = Vec3d::new(0.5 * WIN_WIDTH, 0.5 * WIN_HEIGHT, 1.0);
-        let light_dir = self.light_dir.normalise();
-        let mb = &mut graphics::MeshBuilder::new();
-        let mut to_draw: Vec<Triangle> = Vec::new();
-
-        for i in 0..self.mesh.face_count() {
-            let mut tri = self.mesh.compile_face(i).unwrap();
-
-            // Rotate and translate
-            tri.vertices.0 = Vec3d::mult_mat(&tri.vertices.0, &rot);
-            tri.vertices.1 = Vec3d::mult_mat(&tri.vertices.1, &rot);
-            tri.vertices.2 = Vec3d::mult_mat(&tri.vertices.2, &rot);
-
-            // Offset
-            tri.vertices.0.z += 5.0;
-            tri.vertices.1.z += 5.0;
-            tri.vertices.2.z += 5.0;
-
-            // Calculate the normal
-            let normal = tri.normal().normalise();
-
-            // Check if triangle is visible - less than 90deg to the camera
-            let cam_ray = tri.vertices.0.sub(&self.camera);
-            if Vec3d::dot_product(&normal, &cam_ray) < 0.0 {
-                // Illumination
-                let lum = Vec3d::dot_product(&normal, &light_dir).max(0.1) as f64;
-
-                // Project the triangle
-                tri.vertices.0 = Vec3d::mult_mat(&tri.vertices.0, &self.proj);
-                tri.vertices.1 = Vec3d::mult_mat(&tri.vertices.1, &self.proj);
-                tri.vertices.2 = Vec3d::mult_mat(&tri.vertices.2, &self.proj);
-    
-                tri.vertices.0 = tri.vertices.0.addk(1.0).mul(&scale);
-                tri.vertices.1 = tri.vertices.1.addk(1.0).mul(&scale);
-                tri.vertices.2 = tri.vertices.2.addk(1.0).mul(&scale);
-
-                // Add to vector to draw
-                tri.lum = lum;
-                to_draw.push(tri);
-            }
+/// Attenuation of point and spot lights given on the command line
+const LIGHT_ATTENUATION: (f32, f32, f32) = (1.0, 0.0, 0.05);
+/// Inner and outer cone angles of spot lights given on the command line, in degrees
+const SPOT_CONE: (f32, f32) = (15.0, 25.0);
+
+/// Parse a light of the form `directional:<dir>`, `point:<pos>` or `spot:<pos>:<dir>`, optionally followed by `:<r>,<g>,<b>` (each 0 to 255).
+/// Vectors are of the form `<x>,<y>,<z>`
+fn parse_light(text: &str) -> Result<Light, String> {
+    let triple = |s: &str| -> Result<(f32, f32, f32), String> {
+        let parts = s.split(',').map(|p| p.trim().parse::<f32>()).collect::<Result<Vec<_>, _>>().map_err(|_| format!("Invalid triple {}", s))?;
+        match parts[..] {
+            [a, b, c] => Ok((a, b, c)),
+            _ => Err(format!("Invalid triple {}, expected <a>,<b>,<c>", s)),
         }
+    };
+    let vec = |s: &str| triple(s).map(|(x, y, z)| Vec3d::new(x, y, z));
 
-        // Sort by Z component
-        to_draw.sort_by(|a, b| b.mid_z().partial_cmp(&a.mid_z()).unwrap());
-
-        // Draw triangles
-        for i in 0..to_draw.len() {
-            let tri = &mut to_draw[i];
-
-            // Get points
-            let vs = [
-                vec2(tri.vertices.0.x as f32, tri.vertices.0.y as f32),
-                vec2(tri.vertices.1.x as f32, tri.vertices.1.y as f32),
-                vec2(tri.vertices.2.x as f32, tri.vertices.2.y as f32),
-                vec2(tri.vertices.0.x as f32, tri.vertices.0.y as f32),
-            ];
-
-            // Fill
-            if tri.fill.is_some() {
-                // Apply brightness
-                let rgb = tri.get_fill().unwrap();
-
-                mb.polyline(
-                    graphics::DrawMode::fill(),
-                    &vs,
-                    // graphics::Color::new(lum, lum, lum, 1.0),
-                    Color::new((rgb.red() / 255.0) as f32, (rgb.green() / 255.0) as f32, (rgb.blue() / 255.0) as f32, 1.0),
-                )?;
-            }
-
-            // Stroke
-            if tri.stroke.is_some() {
-                let rgb = tri.get_stroke().unwrap();
-                mb.polyline(
-                    graphics::DrawMode::stroke(2.0),
-                    &vs,
-                    Color::new((rgb.red() / 255.0) as f32, (rgb.green() / 255.0) as f32, (rgb.blue() / 255.0) as f32, 1.0),
-                )?;
-            }
+    let parts: Vec<&str> = text.split(':').collect();
+    let vectors = match parts[0] {
+        "directional" | "point" => 1,
+        "spot" => 2,
+        other => return Err(format!("Invalid light {}, expected directional, point or spot", other)),
+    };
+    if parts.len() != 1 + vectors && parts.len() != 2 + vectors {
+        return Err(format!("Invalid light {}", text));
+    }
+    let color = match parts.get(1 + vectors) {
+        Some(s) => {
+            let (r, g, b) = triple(s)?;
+            colorsys::Rgb::new(r as f64, g as f64, b as f64, None)
         }
+        None => colorsys::Rgb::new(255.0, 255.0, 255.0, None),
+    };
 
-        let mesh = graphics::Mesh::from_data(ctx, mb.build());
-
-        let mut canvas = graphics::Canvas::from_frame(ctx, Color::BLACK);
-        canvas.draw(&mesh, graphics::DrawParam::default());
-        canvas.finish(ctx)?;
+    Ok(match parts[0] {
+        "directional" => Light::Directional { direction: vec(parts[1])?, color, intensity: 1.0 },
+        "point" => Light::Point { position: vec(parts[1])?, color, intensity: 1.0, attenuation: LIGHT_ATTENUATION },
+        _ => Light::Spot {
+            position: vec(parts[1])?,
+            direction: vec(parts[2])?,
+            color,
+            intensity: 1.0,
+            attenuation: LIGHT_ATTENUATION,
+            inner: SPOT_CONE.0.to_radians(),
+            outer: SPOT_CONE.1.to_radians(),
+        },
+    })
+}
 
-        Ok(())
+/// Load a model, choosing the format by extension. Faces without a material are given the default style, and vertices without a normal are given one
+fn load_model(filename: &str) -> Result<Scene, String> {
+    let mut scene = mesh::load_file(filename)?;
+    for mesh in &mut scene.meshes {
+        mesh.default_fill = Some(colorsys::Rgb::new(255.0, 255.0, 128.0, None));
+        mesh.default_stroke = None;
+        mesh.compute_normals();
     }
+    Ok(scene)
 }
-
@@ -0,0 +1,392 @@
+use std::ops::Mul;
+
+use crate::maths::vec::Vec3d;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Mat4x4(pub (f32,f32,f32,f32), pub (f32,f32,f32,f32), pub (f32,f32,f32,f32), pub (f32,f32,f32,f32));
+
+impl Mat4x4 {
+    /// Construct the identity matrix
+    pub fn identity() -> Mat4x4 {
+        Mat4x4(
+            (1.0, 0.0, 0.0, 0.0),
+            (0.0, 1.0, 0.0, 0.0),
+            (0.0, 0.0, 1.0, 0.0),
+            (0.0, 0.0, 0.0, 1.0),
+        )
+    }
+
+    /// Construct a translation matrix
+    pub fn translation(x: f32, y: f32, z: f32) -> Mat4x4 {
+        Mat4x4(
+            (1.0, 0.0, 0.0, 0.0),
+            (0.0, 1.0, 0.0, 0.0),
+            (0.0, 0.0, 1.0, 0.0),
+            (x, y, z, 1.0),
+        )
+    }
+
+    /// Construct a scaling matrix
+    pub fn scale(x: f32, y: f32, z: f32) -> Mat4x4 {
+        Mat4x4(
+            (x, 0.0, 0.0, 0.0),
+            (0.0, y, 0.0, 0.0),
+            (0.0, 0.0, z, 0.0),
+            (0.0, 0.0, 0.0, 1.0),
+        )
+    }
+
+    /// Construct a 3D rotation matrix around the X axis. Theta in radians
+    pub fn rot_x(theta: f32) -> Mat4x4 {
+        Mat4x4(
+            (1.0, 0.0, 0.0, 0.0),
+            (0.0, theta.cos(), -theta.sin(), 0.0),
+            (0.0, theta.sin(), theta.cos(), 0.0),
+            (0.0, 0.0, 0.0, 1.0),
+        )
+    }
+
+    /// Construct a 3D rotation matrix around the Y axis. Theta in radians
+    pub fn rot_y(theta: f32) -> Mat4x4 {
+        Mat4x4(
+            (theta.cos(), 0.0, theta.sin(), 0.0),
+            (0.0, 1.0, 0.0, 0.0),
+            (-theta.sin(), 0.0, theta.cos(), 0.0),
+            (0.0, 0.0, 0.0, 1.0),
+        )
+    }
+
+    /// Construct a 3D rotation matrix around the Z axis. Theta in radians
+    pub fn rot_z(theta: f32) -> Mat4x4 {
+        Mat4x4(
+            (theta.cos(), -theta.sin(), 0.0, 0.0),
+            (theta.sin(), theta.cos(), 0.0, 0.0),
+            (0.0, 0.0, 1.0, 0.0),
+            (0.0, 0.0, 0.0, 1.0),
+        )
+    }
+
+    /// Construct a 3D rotation matrix around `axis` (which need not be normalised), turning the same way as `rot_x`, `rot_y` and `rot_z`.
+    /// Theta in radians
+    pub fn axis_angle(axis: &Vec3d, theta: f32) -> Mat4x4 {
+        let a = axis.normalise();
+        let (s, c) = theta.sin_cos();
+        let t = 1.0 - c;
+        Mat4x4(
+            (t * a.x * a.x + c, t * a.x * a.y - s * a.z, t * a.x * a.z + s * a.y, 0.0),
+            (t * a.x * a.y + s * a.z, t * a.y * a.y + c, t * a.y * a.z - s * a.x, 0.0),
+            (t * a.x * a.z - s * a.y, t * a.y * a.z + s * a.x, t * a.z * a.z + c, 0.0),
+            (0.0, 0.0, 0.0, 1.0),
+        )
+    }
+
+    /// Construct a perspective projection matrix. `fov` is the vertical field of view in radians, and `aspect` is width divided by height.
+    /// Depth becomes 0 at `near` and 1 at `far`
+    pub fn perspective(fov: f32, aspect: f32, near: f32, far: f32) -> Mat4x4 {
+        let f = 1.0 / (fov * 0.5).tan();
+        Mat4x4(
+            (f / aspect, 0.0, 0.0, 0.0),
+            (0.0, f, 0.0, 0.0),
+            (0.0, 0.0, far / (far - near), 1.0),
+            (0.0, 0.0, (-far * near) / (far - near), 0.0),
+        )
+    }
+
+    /// Construct an orthographic projection matrix, which maps the box with the given bounds onto -1 to 1 in `x` and `y`.
+    /// Depth becomes 0 at `near` and 1 at `far`
+    pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Mat4x4 {
+        Mat4x4(
+            (2.0 / (right - left), 0.0, 0.0, 0.0),
+            (0.0, 2.0 / (top - bottom), 0.0, 0.0),
+            (0.0, 0.0, 1.0 / (far - near), 0.0),
+            (-(right + left) / (right - left), -(top + bottom) / (top - bottom), -near / (far - near), 1.0),
+        )
+    }
+
+    /// Does this matrix leave `w` alone, as an orthographic projection (or any affine transform) does?
+    pub fn is_affine(&self) -> bool {
+        self.0.3 == 0.0 && self.1.3 == 0.0 && self.2.3 == 0.0 && self.3.3 == 1.0
+    }
+
+    /// Construct a matrix which places an object at `pos`, facing `target`, with `up` as its rough upwards direction
+    pub fn point_at(pos: &Vec3d, target: &Vec3d, up: &Vec3d) -> Mat4x4 {
+        // New forward direction
+        let forward = (*target - *pos).normalise();
+
+        // New up direction, made perpendicular to forward
+        let up = (*up - forward * Vec3d::dot_product(up, &forward)).normalise();
+
+        // New right direction
+        let right = Vec3d::normal(&up, &forward);
+
+        Mat4x4(
+            (right.x, right.y, right.z, 0.0),
+            (up.x, up.y, up.z, 0.0),
+            (forward.x, forward.y, forward.z, 0.0),
+            (pos.x, pos.y, pos.z, 1.0),
+        )
+    }
+
+    /// Invert a matrix which only rotates and translates (such as one from `point_at`). Not valid for any other matrix
+    pub fn quick_inverse(&self) -> Mat4x4 {
+        let m = self;
+        Mat4x4(
+            (m.0.0, m.1.0, m.2.0, 0.0),
+            (m.0.1, m.1.1, m.2.1, 0.0),
+            (m.0.2, m.1.2, m.2.2, 0.0),
+            (
+                -(m.3.0 * m.0.0 + m.3.1 * m.0.1 + m.3.2 * m.0.2),
+                -(m.3.0 * m.1.0 + m.3.1 * m.1.1 + m.3.2 * m.1.2),
+                -(m.3.0 * m.2.0 + m.3.1 * m.2.1 + m.3.2 * m.2.2),
+                1.0,
+            ),
+        )
+    }
+
+    /// Get the elements as an array of rows
+    fn rows(&self) -> [[f32; 4]; 4] {
+        let row = |r: (f32, f32, f32, f32)| [r.0, r.1, r.2, r.3];
+        [row(self.0), row(self.1), row(self.2), row(self.3)]
+    }
+
+    /// Construct a matrix from an array of rows
+    fn from_rows(m: [[f32; 4]; 4]) -> Mat4x4 {
+        let row = |r: [f32; 4]| (r[0], r[1], r[2], r[3]);
+        Mat4x4(row(m[0]), row(m[1]), row(m[2]), row(m[3]))
+    }
+
+    /// Swap rows and columns
+    pub fn transpose(&self) -> Mat4x4 {
+        let m = self.rows();
+        Mat4x4::from_rows([0, 1, 2, 3].map(|i| [m[0][i], m[1][i], m[2][i], m[3][i]]))
+    }
+
+    /// Reduce `m` to upper triangular form by Gaussian elimination with partial pivoting, applying the same row operations to `other`.
+    /// Return the sign the row swaps give the determinant, or None if the matrix is singular
+    fn eliminate(m: &mut [[f32; 4]; 4], other: &mut [[f32; 4]; 4]) -> Option<f32> {
+        let mut sign = 1.0;
+        for col in 0..4 {
+            let pivot = (col..4).max_by(|&a, &b| m[a][col].abs().total_cmp(&m[b][col].abs())).unwrap();
+            if m[pivot][col].abs() < f32::EPSILON {
+                return None;
+            }
+            if pivot != col {
+                m.swap(pivot, col);
+                other.swap(pivot, col);
+                sign = -sign;
+            }
+            for row in col + 1..4 {
+                let k = m[row][col] / m[col][col];
+                for i in 0..4 {
+                    m[row][i] -= k * m[col][i];
+                    other[row][i] -= k * other[col][i];
+                }
+            }
+        }
+        Some(sign)
+    }
+
+    /// Get the determinant
+    pub fn determinant(&self) -> f32 {
+        let mut m = self.rows();
+        match Mat4x4::eliminate(&mut m, &mut [[0.0; 4]; 4]) {
+            Some(sign) => sign * m[0][0] * m[1][1] * m[2][2] * m[3][3],
+            None => 0.0,
+        }
+    }
+
+    /// Get the inverse, or None if the matrix is singular. For matrices which only rotate and translate, `quick_inverse` is cheaper
+    pub fn inverse(&self) -> Option<Mat4x4> {
+        let mut m = self.rows();
+        let mut inv = Mat4x4::identity().rows();
+        Mat4x4::eliminate(&mut m, &mut inv)?;
+
+        // Back substitute, from the bottom row up
+        for col in (0..4).rev() {
+            let k = m[col][col];
+            for i in 0..4 {
+                m[col][i] /= k;
+                inv[col][i] /= k;
+            }
+            for row in 0..col {
+                let k = m[row][col];
+                for i in 0..4 {
+                    m[row][i] -= k * m[col][i];
+                    inv[row][i] -= k * inv[col][i];
+                }
+            }
+        }
+        Some(Mat4x4::from_rows(inv))
+    }
+
+    /// Get the matrix which carries normals through this transform, with `Vec3d::mult_mat_dir`. This is the inverse transpose, which keeps
+    /// normals perpendicular to their surfaces under non-uniform scaling. If the matrix is singular it is returned as it is
+    pub fn normal_matrix(&self) -> Mat4x4 {
+        self.inverse().map_or_else(|| *self, |inv| inv.transpose())
+    }
+
+    /// Multiply two matrices
+    pub fn mult(mat1: &Mat4x4, mat2: &Mat4x4) -> Mat4x4 {
+        Mat4x4(
+            (
+                mat1.0.0 * mat2.0.0 + mat1.0.1 * mat2.1.0 + mat1.0.2 * mat2.2.0 + mat1.0.3 * mat2.3.0,
+                mat1.0.0 * mat2.0.1 + mat1.0.1 * mat2.1.1 + mat1.0.2 * mat2.2.1 + mat1.0.3 * mat2.3.1,
+                mat1.0.0 * mat2.0.2 + mat1.0.1 * mat2.1.2 + mat1.0.2 * mat2.2.2 + mat1.0.3 * mat2.3.2,
+                mat1.0.0 * mat2.0.3 + mat1.0.1 * mat2.1.3 + mat1.0.2 * mat2.2.3 + mat1.0.3 * mat2.3.3,
+            ),
+            (
+                mat1.1.0 * mat2.0.0 + mat1.1.1 * mat2.1.0 + mat1.1.2 * mat2.2.0 + mat1.1.3 * mat2.3.0,
+                mat1.1.0 * mat2.0.1 + mat1.1.1 * mat2.1.1 + mat1.1.2 * mat2.2.1 + mat1.1.3 * mat2.3.1,
+                mat1.1.0 * mat2.0.2 + mat1.1.1 * mat2.1.2 + mat1.1.2 * mat2.2.2 + mat1.1.3 * mat2.3.2,
+                mat1.1.0 * mat2.0.3 + mat1.1.1 * mat2.1.3 + mat1.1.2 * mat2.2.3 + mat1.1.3 * mat2.3.3,
+            ),
+            (
+                mat1.2.0 * mat2.0.0 + mat1.2.1 * mat2.1.0 + mat1.2.2 * mat2.2.0 + mat1.2.3 * mat2.3.0,
+                mat1.2.0 * mat2.0.1 + mat1.2.1 * mat2.1.1 + mat1.2.2 * mat2.2.1 + mat1.2.3 * mat2.3.1,
+                mat1.2.0 * mat2.0.2 + mat1.2.1 * mat2.1.2 + mat1.2.2 * mat2.2.2 + mat1.2.3 * mat2.3.2,
+                mat1.2.0 * mat2.0.3 + mat1.2.1 * mat2.1.3 + mat1.2.2 * mat2.2.3 + mat1.2.3 * mat2.3.3,
+            ),
+            (
+                mat1.3.0 * mat2.0.0 + mat1.3.1 * mat2.1.0 + mat1.3.2 * mat2.2.0 + mat1.3.3 * mat2.3.0,
+                mat1.3.0 * mat2.0.1 + mat1.3.1 * mat2.1.1 + mat1.3.2 * mat2.2.1 + mat1.3.3 * mat2.3.1,
+                mat1.3.0 * mat2.0.2 + mat1.3.1 * mat2.1.2 + mat1.3.2 * mat2.2.2 + mat1.3.3 * mat2.3.2,
+                mat1.3.0 * mat2.0.3 + mat1.3.1 * mat2.1.3 + mat1.3.2 * mat2.2.3 + mat1.3.3 * mat2.3.3,
+            ),
+        )
+    }
+}
+
+/// Multiply two matrices, as `Mat4x4::mult`. The product applies the left matrix, then the right
+impl Mul for Mat4x4 {
+    type Output = Mat4x4;
+
+    fn mul(self, rhs: Mat4x4) -> Mat4x4 {
+        Mat4x4::mult(&self, &rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maths::vec::Vec4d;
+    use crate::test_util::assert_vec;
+
+    /// Project a point, then divide by `w`
+    fn project(x: f32, y: f32, z: f32, proj: &Mat4x4) -> Vec3d {
+        (Vec4d::from_point(&Vec3d::new(x, y, z)) * *proj).divide()
+    }
+
+    fn assert_mat(a: &Mat4x4, b: &Mat4x4) {
+        let (a, b) = (a.rows(), b.rows());
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!((a[i][j] - b[i][j]).abs() < 1e-5, "{:?} != {:?}", a, b);
+            }
+        }
+    }
+
+    /// A matrix with no special structure, and its inverse
+    fn general() -> (Mat4x4, Mat4x4) {
+        let m = Mat4x4((2.0, 0.0, 1.0, 0.0), (1.0, 3.0, 0.0, 0.0), (0.0, 1.0, 4.0, 1.0), (0.0, 0.0, 1.0, 1.0));
+        let inv = Mat4x4::from_rows([
+            [9.0, 1.0, -3.0, 3.0],
+            [-3.0, 6.0, 1.0, -1.0],
+            [1.0, -2.0, 6.0, -6.0],
+            [-1.0, 2.0, -6.0, 25.0],
+        ].map(|row| row.map(|x| x / 19.0)));
+        (m, inv)
+    }
+
+    #[test]
+    fn identity_translation_scale() {
+        let v = Vec3d::new(1.0, -2.0, 3.0);
+        assert_vec(&Vec3d::mult_mat(&v, &Mat4x4::identity()), 1.0, -2.0, 3.0);
+        assert_vec(&Vec3d::mult_mat(&v, &Mat4x4::translation(1.0, 2.0, 3.0)), 2.0, 0.0, 6.0);
+        assert_vec(&Vec3d::mult_mat(&v, &Mat4x4::scale(2.0, 3.0, -1.0)), 2.0, -6.0, -3.0);
+        // Scaling then translating, and the other way around
+        assert_vec(&Vec3d::mult_mat(&v, &Mat4x4::mult(&Mat4x4::scale(2.0, 2.0, 2.0), &Mat4x4::translation(1.0, 0.0, 0.0))), 3.0, -4.0, 6.0);
+        assert_vec(&Vec3d::mult_mat(&v, &Mat4x4::mult(&Mat4x4::translation(1.0, 0.0, 0.0), &Mat4x4::scale(2.0, 2.0, 2.0))), 4.0, -4.0, 6.0);
+        assert_mat(&Mat4x4::mult(&general().0, &Mat4x4::identity()), &general().0);
+    }
+
+    #[test]
+    fn transpose() {
+        let m = Mat4x4((1.0, 2.0, 3.0, 4.0), (5.0, 6.0, 7.0, 8.0), (9.0, 10.0, 11.0, 12.0), (13.0, 14.0, 15.0, 16.0));
+        let t = Mat4x4((1.0, 5.0, 9.0, 13.0), (2.0, 6.0, 10.0, 14.0), (3.0, 7.0, 11.0, 15.0), (4.0, 8.0, 12.0, 16.0));
+        assert_mat(&m.transpose(), &t);
+        assert_mat(&m.transpose().transpose(), &m);
+    }
+
+    #[test]
+    fn determinant() {
+        assert_eq!(Mat4x4::identity().determinant(), 1.0);
+        assert!((Mat4x4::scale(2.0, 3.0, 4.0).determinant() - 24.0).abs() < 1e-5);
+        assert!((general().0.determinant() - 19.0).abs() < 1e-4);
+        // Swapping two rows flips the sign, and a repeated row gives zero
+        let (m, _) = general();
+        assert!((Mat4x4(m.1, m.0, m.2, m.3).determinant() + 19.0).abs() < 1e-4);
+        assert_eq!(Mat4x4(m.0, m.0, m.2, m.3).determinant(), 0.0);
+        // Rotations preserve volume
+        assert!((Mat4x4::axis_angle(&Vec3d::new(1.0, 2.0, 3.0), 0.7).determinant() - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn inverse() {
+        let (m, inv) = general();
+        assert_mat(&m.inverse().unwrap(), &inv);
+        assert_mat(&Mat4x4::mult(&m, &inv), &Mat4x4::identity());
+        assert_mat(&Mat4x4::translation(1.0, 2.0, 3.0).inverse().unwrap(), &Mat4x4::translation(-1.0, -2.0, -3.0));
+        assert_mat(&Mat4x4::scale(2.0, 4.0, 0.5).inverse().unwrap(), &Mat4x4::scale(0.5, 0.25, 2.0));
+        // Agrees with the quick inverse where that is valid
+        let look = Mat4x4::point_at(&Vec3d::new(1.0, 2.0, 3.0), &Vec3d::new(-1.0, 0.0, 2.0), &Vec3d::unit_y());
+        assert_mat(&look.inverse().unwrap(), &look.quick_inverse());
+        assert!(Mat4x4::scale(1.0, 0.0, 1.0).inverse().is_none());
+    }
+
+    #[test]
+    fn normal_matrix() {
+        // Stretching along x tilts the plane x = y towards the y axis, so its normal tilts the other way
+        let normal = Vec3d::mult_mat_dir(&Vec3d::new(1.0, -1.0, 0.0), &Mat4x4::scale(2.0, 1.0, 1.0).normal_matrix());
+        assert_vec(&normal, 0.5, -1.0, 0.0);
+        let rotation = Mat4x4::axis_angle(&Vec3d::new(1.0, 2.0, 3.0), 0.7);
+        assert_mat(&rotation.normal_matrix(), &rotation);
+        assert_mat(&Mat4x4::scale(1.0, 0.0, 1.0).normal_matrix(), &Mat4x4::scale(1.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn axis_angle() {
+        for theta in [0.3, 1.0, -2.0] {
+            assert_mat(&Mat4x4::axis_angle(&Vec3d::unit_x(), theta), &Mat4x4::rot_x(theta));
+            assert_mat(&Mat4x4::axis_angle(&Vec3d::unit_y(), theta), &Mat4x4::rot_y(theta));
+            assert_mat(&Mat4x4::axis_angle(&Vec3d::unit_z(), theta), &Mat4x4::rot_z(theta));
+        }
+        // A third of a turn around the diagonal swaps the axes around
+        let m = Mat4x4::axis_angle(&Vec3d::diag(1.0), 120.0f32.to_radians());
+        assert_vec(&Vec3d::mult_mat(&Vec3d::unit_x(), &m), 0.0, 0.0, 1.0);
+        assert_vec(&Vec3d::mult_mat(&Vec3d::unit_z(), &m), 0.0, 1.0, 0.0);
+        // Points on the axis stay put
+        assert_vec(&Vec3d::mult_mat(&Vec3d::diag(2.0), &m), 2.0, 2.0, 2.0);
+    }
+
+    #[test]
+    fn perspective() {
+        let proj = Mat4x4::perspective(90.0f32.to_radians(), 2.0, 1.0, 11.0);
+        assert!(!proj.is_affine());
+        // The top right corner of the view at each depth maps to (1, 1)
+        assert_vec(&project(2.0, 1.0, 1.0, &proj), 1.0, 1.0, 0.0);
+        assert_vec(&project(22.0, 11.0, 11.0, &proj), 1.0, 1.0, 1.0);
+        // Half way across at twice the distance
+        assert_vec(&project(2.0, -1.0, 2.0, &proj), 0.5, -0.5, 11.0 / 20.0);
+    }
+
+    #[test]
+    fn orthographic() {
+        let proj = Mat4x4::orthographic(-4.0, 2.0, -1.0, 3.0, 1.0, 5.0);
+        assert!(proj.is_affine());
+        assert_vec(&project(-4.0, -1.0, 1.0, &proj), -1.0, -1.0, 0.0);
+        assert_vec(&project(2.0, 3.0, 5.0, &proj), 1.0, 1.0, 1.0);
+        // Depth does not change the size of things
+        assert_vec(&project(-1.0, 1.0, 3.0, &proj), 0.0, 0.0, 0.5);
+        assert_vec(&project(-1.0, 1.0, 4.0, &proj), 0.0, 0.0, 0.75);
+    }
+}
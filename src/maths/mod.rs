@@ -0,0 +1,3 @@
+pub mod mat;
+pub mod quat;
+pub mod vec;
\ No newline at end of file
@@ -0,0 +1,228 @@
+use super::mat::Mat4x4;
+use super::vec::Vec3d;
+
+/// Quaternion `w + xi + yj + zk`. Unit quaternions represent rotations, turning the same way as `Mat4x4::axis_angle`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Quat {
+    pub w: f32,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl Quat {
+    /// Construct a quaternion from its components
+    pub fn new(w: f32, x: f32, y: f32, z: f32) -> Quat {
+        Quat { w, x, y, z }
+    }
+
+    /// Construct the quaternion which does not rotate
+    pub fn identity() -> Quat {
+        Quat::new(1.0, 0.0, 0.0, 0.0)
+    }
+
+    /// Construct a rotation around `axis` (which need not be normalised). Theta in radians
+    pub fn from_axis_angle(axis: &Vec3d, theta: f32) -> Quat {
+        let a = axis.normalise();
+        let (s, c) = (theta * 0.5).sin_cos();
+        Quat::new(c, a.x * s, a.y * s, a.z * s)
+    }
+
+    /// Multiply two quaternions. The result rotates by `q1`, then by `q2`, matching `Mat4x4::mult`
+    pub fn mult(q1: &Quat, q2: &Quat) -> Quat {
+        Quat::new(
+            q1.w * q2.w - q1.x * q2.x - q1.y * q2.y - q1.z * q2.z,
+            q1.w * q2.x + q1.x * q2.w + q1.y * q2.z - q1.z * q2.y,
+            q1.w * q2.y - q1.x * q2.z + q1.y * q2.w + q1.z * q2.x,
+            q1.w * q2.z + q1.x * q2.y - q1.y * q2.x + q1.z * q2.w,
+        )
+    }
+
+    /// Get the conjugate, which is the inverse rotation of a unit quaternion
+    pub fn conjugate(&self) -> Quat {
+        Quat::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    /// Dot product of two quaternions
+    pub fn dot(q1: &Quat, q2: &Quat) -> f32 {
+        q1.w * q2.w + q1.x * q2.x + q1.y * q2.y + q1.z * q2.z
+    }
+
+    /// Get the length
+    pub fn length(&self) -> f32 {
+        Quat::dot(self, self).sqrt()
+    }
+
+    /// Get the quaternion with the same direction, but of length 1
+    pub fn normalise(&self) -> Quat {
+        let l = self.length();
+        Quat::new(self.w / l, self.x / l, self.y / l, self.z / l)
+    }
+
+    /// Spherically interpolate between two rotations, turning at a constant rate along the shortest path. `t` runs from 0 at `q1` to 1 at `q2`
+    pub fn slerp(q1: &Quat, q2: &Quat, t: f32) -> Quat {
+        // q and -q are the same rotation; pick whichever is nearer q1
+        let mut cos = Quat::dot(q1, q2);
+        let q2 = if cos < 0.0 {
+            cos = -cos;
+            Quat::new(-q2.w, -q2.x, -q2.y, -q2.z)
+        } else {
+            *q2
+        };
+
+        // Nearly equal rotations would divide by almost zero, so interpolate linearly
+        let (k1, k2) = if cos > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = cos.acos();
+            let sin = theta.sin();
+            (((1.0 - t) * theta).sin() / sin, (t * theta).sin() / sin)
+        };
+        Quat::new(
+            k1 * q1.w + k2 * q2.w,
+            k1 * q1.x + k2 * q2.x,
+            k1 * q1.y + k2 * q2.y,
+            k1 * q1.z + k2 * q2.z,
+        )
+        .normalise()
+    }
+
+    /// Get the rotation matrix of a unit quaternion
+    pub fn to_mat4x4(&self) -> Mat4x4 {
+        let Quat { w, x, y, z } = *self;
+        Mat4x4(
+            (1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - w * z), 2.0 * (x * z + w * y), 0.0),
+            (2.0 * (x * y + w * z), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - w * x), 0.0),
+            (2.0 * (x * z - w * y), 2.0 * (y * z + w * x), 1.0 - 2.0 * (x * x + y * y), 0.0),
+            (0.0, 0.0, 0.0, 1.0),
+        )
+    }
+
+    /// Get the unit quaternion of a rotation matrix. Any translation is ignored
+    pub fn from_mat4x4(m: &Mat4x4) -> Quat {
+        let (m00, m01, m02) = (m.0.0, m.0.1, m.0.2);
+        let (m10, m11, m12) = (m.1.0, m.1.1, m.1.2);
+        let (m20, m21, m22) = (m.2.0, m.2.1, m.2.2);
+
+        // Solve for the largest component first, so it is never divided by something near zero
+        let trace = m00 + m11 + m22;
+        let q = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Quat::new(0.25 * s, (m21 - m12) / s, (m02 - m20) / s, (m10 - m01) / s)
+        } else if m00 > m11 && m00 > m22 {
+            let s = (1.0 + m00 - m11 - m22).sqrt() * 2.0;
+            Quat::new((m21 - m12) / s, 0.25 * s, (m01 + m10) / s, (m02 + m20) / s)
+        } else if m11 > m22 {
+            let s = (1.0 + m11 - m00 - m22).sqrt() * 2.0;
+            Quat::new((m02 - m20) / s, (m01 + m10) / s, 0.25 * s, (m12 + m21) / s)
+        } else {
+            let s = (1.0 + m22 - m00 - m11).sqrt() * 2.0;
+            Quat::new((m10 - m01) / s, (m02 + m20) / s, (m12 + m21) / s, 0.25 * s)
+        };
+        q.normalise()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    fn assert_quat(q: &Quat, w: f32, x: f32, y: f32, z: f32) {
+        assert!((q.w - w).abs() < 1e-5 && (q.x - x).abs() < 1e-5 && (q.y - y).abs() < 1e-5 && (q.z - z).abs() < 1e-5, "{:?} != ({}, {}, {}, {})", q, w, x, y, z);
+    }
+
+    /// Assert `q` is the same rotation as `r`, which may have the opposite sign
+    fn assert_rotation(q: &Quat, r: &Quat) {
+        let r = if Quat::dot(q, r) < 0.0 { Quat::new(-r.w, -r.x, -r.y, -r.z) } else { *r };
+        assert_quat(q, r.w, r.x, r.y, r.z);
+    }
+
+    fn assert_mat(a: &Mat4x4, b: &Mat4x4) {
+        let rows = |m: &Mat4x4| [m.0, m.1, m.2, m.3].map(|r| [r.0, r.1, r.2, r.3]);
+        let (a, b) = (rows(a), rows(b));
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!((a[i][j] - b[i][j]).abs() < 1e-5, "{:?} != {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn axis_angle() {
+        assert_quat(&Quat::from_axis_angle(&Vec3d::unit_z(), PI), 0.0, 0.0, 0.0, 1.0);
+        let h = 0.5f32.sqrt();
+        assert_quat(&Quat::from_axis_angle(&Vec3d::new(0.0, 3.0, 0.0), FRAC_PI_2), h, 0.0, h, 0.0);
+        assert_quat(&Quat::from_axis_angle(&Vec3d::unit_x(), 0.0), 1.0, 0.0, 0.0, 0.0);
+    }
+
+    #[test]
+    fn algebra() {
+        let (i, j, k) = (Quat::new(0.0, 1.0, 0.0, 0.0), Quat::new(0.0, 0.0, 1.0, 0.0), Quat::new(0.0, 0.0, 0.0, 1.0));
+        assert_quat(&Quat::mult(&i, &j), 0.0, 0.0, 0.0, 1.0);
+        assert_quat(&Quat::mult(&j, &i), 0.0, 0.0, 0.0, -1.0);
+        assert_quat(&Quat::mult(&Quat::mult(&i, &j), &k), -1.0, 0.0, 0.0, 0.0);
+
+        let q = Quat::new(1.0, 2.0, 3.0, 4.0);
+        assert_quat(&q.conjugate(), 1.0, -2.0, -3.0, -4.0);
+        assert!((q.length() - 30.0f32.sqrt()).abs() < 1e-5);
+        assert!((q.normalise().length() - 1.0).abs() < 1e-5);
+        assert_eq!(Quat::dot(&q, &Quat::new(1.0, 1.0, 0.0, -1.0)), -1.0);
+        // A rotation followed by its inverse does nothing
+        let q = q.normalise();
+        assert_quat(&Quat::mult(&q, &q.conjugate()), 1.0, 0.0, 0.0, 0.0);
+        assert_quat(&Quat::mult(&q, &Quat::identity()), q.w, q.x, q.y, q.z);
+    }
+
+    #[test]
+    fn to_mat4x4() {
+        assert_mat(&Quat::identity().to_mat4x4(), &Mat4x4::identity());
+        for theta in [0.3, 1.0, -2.0] {
+            assert_mat(&Quat::from_axis_angle(&Vec3d::unit_x(), theta).to_mat4x4(), &Mat4x4::rot_x(theta));
+            assert_mat(&Quat::from_axis_angle(&Vec3d::unit_y(), theta).to_mat4x4(), &Mat4x4::rot_y(theta));
+            assert_mat(&Quat::from_axis_angle(&Vec3d::unit_z(), theta).to_mat4x4(), &Mat4x4::rot_z(theta));
+            let axis = Vec3d::new(1.0, -2.0, 0.5);
+            assert_mat(&Quat::from_axis_angle(&axis, theta).to_mat4x4(), &Mat4x4::axis_angle(&axis, theta));
+        }
+
+        // Multiplying quaternions composes rotations in the same order as multiplying matrices
+        let (a, b) = (Quat::from_axis_angle(&Vec3d::unit_x(), 0.4), Quat::from_axis_angle(&Vec3d::new(0.0, 1.0, 1.0), 1.3));
+        assert_mat(&Quat::mult(&a, &b).to_mat4x4(), &Mat4x4::mult(&a.to_mat4x4(), &b.to_mat4x4()));
+    }
+
+    #[test]
+    fn from_mat4x4() {
+        // Rotations by up to a full turn exercise each branch
+        for axis in [Vec3d::unit_x(), Vec3d::unit_y(), Vec3d::unit_z(), Vec3d::new(1.0, 2.0, -3.0)] {
+            for theta in [0.0, 0.5, 2.0, PI, 4.0] {
+                let q = Quat::from_axis_angle(&axis, theta);
+                assert_rotation(&Quat::from_mat4x4(&q.to_mat4x4()), &q);
+            }
+        }
+        // Translation is ignored
+        let m = Mat4x4::mult(&Mat4x4::rot_y(1.0), &Mat4x4::translation(1.0, 2.0, 3.0));
+        assert_rotation(&Quat::from_mat4x4(&m), &Quat::from_axis_angle(&Vec3d::unit_y(), 1.0));
+    }
+
+    #[test]
+    fn slerp() {
+        let a = Quat::from_axis_angle(&Vec3d::unit_z(), 0.0);
+        let b = Quat::from_axis_angle(&Vec3d::unit_z(), FRAC_PI_2);
+        assert_quat(&Quat::slerp(&a, &b, 0.0), a.w, a.x, a.y, a.z);
+        assert_quat(&Quat::slerp(&a, &b, 1.0), b.w, b.x, b.y, b.z);
+        // Turns at a constant rate
+        for t in [0.25, 0.5, 0.9] {
+            assert_rotation(&Quat::slerp(&a, &b, t), &Quat::from_axis_angle(&Vec3d::unit_z(), t * FRAC_PI_2));
+        }
+        // Takes the shorter way round: -b is the same rotation as b
+        let nb = Quat::new(-b.w, -b.x, -b.y, -b.z);
+        assert_rotation(&Quat::slerp(&a, &nb, 0.5), &Quat::from_axis_angle(&Vec3d::unit_z(), PI / 4.0));
+        // From 7/8 of a turn to 1/8 goes through zero, not a half turn
+        let c = Quat::from_axis_angle(&Vec3d::unit_y(), 1.75 * PI);
+        let d = Quat::from_axis_angle(&Vec3d::unit_y(), 0.25 * PI);
+        assert_rotation(&Quat::slerp(&c, &d, 0.5), &Quat::identity());
+        // Nearly equal rotations
+        let e = Quat::from_axis_angle(&Vec3d::unit_z(), 1e-4);
+        assert_rotation(&Quat::slerp(&a, &e, 0.5), &Quat::from_axis_angle(&Vec3d::unit_z(), 5e-5));
+    }
+}
@@ -0,0 +1,341 @@
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use crate::maths::mat::Mat4x4;
+
+// 3 dimensional vector
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Vec3d {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl Vec3d {
+    /// Construct a new 3D vector
+    pub fn new(x: f32, y: f32, z: f32) -> Vec3d {
+        Vec3d { x, y, z }
+    }
+
+    /// Construct a diagonal vector (vector where all components are equal)
+    pub fn diag(k: f32) -> Vec3d {
+        Vec3d::new(k, k, k)
+    }
+
+    /// Is this equal to the given vertex?
+    pub fn equals(&self, other: &Vec3d) -> bool {
+        return self.x == other.x && self.y == other.y && self.z == other.z;
+    }
+
+    /// Return the origin
+    pub fn origin() -> Vec3d {
+        Vec3d::new(0.0, 0.0, 0.0)
+    }
+
+    /// Return the unit vector
+    pub fn unit() -> Vec3d {
+        Vec3d::new(1.0, 1.0, 1.0)
+    }
+
+    /// Return unit vector in `x` direction
+    pub fn unit_x() -> Vec3d {
+        Vec3d::new(1.0, 0.0, 0.0)
+    }
+
+    /// Return unit vector in `y` direction
+    pub fn unit_y() -> Vec3d {
+        Vec3d::new(0.0, 1.0, 0.0)
+    }
+
+    /// Return unit vector in `z` direction
+    pub fn unit_z() -> Vec3d {
+        Vec3d::new(0.0, 0.0, 1.0)
+    }
+
+    /// Add a scalar and self
+    pub fn addk(&self, k: f32) -> Vec3d {
+        Vec3d::new(
+            self.x + k,
+            self.y + k,
+            self.z + k,
+        )
+    }
+
+    /// Add to x component
+    pub fn add_x(&self, x: f32) -> Vec3d {
+        Vec3d::new(
+            self.x + x,
+            self.y,
+            self.z,
+        )
+    }
+
+    /// Add to y component
+    pub fn add_y(&self, y: f32) -> Vec3d {
+        Vec3d::new(
+            self.x,
+            self.y + y,
+            self.z,
+        )
+    }
+
+    /// Add to x component
+    pub fn add_z(&self, z: f32) -> Vec3d {
+        Vec3d::new(
+            self.x,
+            self.y,
+            self.z + z,
+        )
+    }
+
+    /// Multiple self by a vector (x' = x1 * x2, ...)
+    pub fn mul(&self, vec: &Vec3d) -> Vec3d {
+        Vec3d::new(
+            self.x * vec.x,
+            self.y * vec.y,
+            self.z * vec.z,
+        )
+    }
+
+    /// Multiply a point by an affine matrix, such as a model or view transform. Projections change `w`, so must be applied to a `Vec4d` instead
+    pub fn mult_mat(vec: &Vec3d, mat: &Mat4x4) -> Vec3d {
+        debug_assert!(mat.is_affine(), "projective matrix applied to a Vec3d");
+        Vec3d::new(
+            vec.x * mat.0.0 + vec.y * mat.1.0 + vec.z * mat.2.0 + mat.3.0,
+            vec.x * mat.0.1 + vec.y * mat.1.1 + vec.z * mat.2.1 + mat.3.1,
+            vec.x * mat.0.2 + vec.y * mat.1.2 + vec.z * mat.2.2 + mat.3.2,
+        )
+    }
+
+    /// Multiply a direction by a matrix, ignoring translation and projection
+    pub fn mult_mat_dir(vec: &Vec3d, mat: &Mat4x4) -> Vec3d {
+        Vec3d::new(
+            vec.x * mat.0.0 + vec.y * mat.1.0 + vec.z * mat.2.0,
+            vec.x * mat.0.1 + vec.y * mat.1.1 + vec.z * mat.2.1,
+            vec.x * mat.0.2 + vec.y * mat.1.2 + vec.z * mat.2.2,
+        )
+    }
+
+    /// Return normal of two vectors
+    pub fn normal(v1: &Vec3d, v2: &Vec3d) -> Vec3d {
+        Vec3d::new(
+            v1.y * v2.z - v1.z * v2.y,
+            v1.z * v2.x - v1.x * v2.z,
+            v1.x * v2.y - v1.y * v2.x,
+        )
+    }
+
+    /// Return the dot product between two vectors
+    pub fn dot_product(v1: &Vec3d, v2: &Vec3d) -> f32 {
+        v1.x * v2.x + v1.y * v2.y + v1.z * v2.z
+    }
+
+    /// Return length of self
+    pub fn length(&self) -> f32 {
+        (self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
+    }
+
+    /// Return a normalised varient of this vector
+    pub fn normalise(&self) -> Vec3d {
+        let length = self.length();
+        Vec3d::new(self.x / length, self.y / length, self.z / length)
+    }
+}
+
+impl Add for Vec3d {
+    type Output = Vec3d;
+
+    fn add(self, rhs: Vec3d) -> Vec3d {
+        Vec3d::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl Sub for Vec3d {
+    type Output = Vec3d;
+
+    fn sub(self, rhs: Vec3d) -> Vec3d {
+        Vec3d::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl Mul<f32> for Vec3d {
+    type Output = Vec3d;
+
+    fn mul(self, k: f32) -> Vec3d {
+        Vec3d::new(self.x * k, self.y * k, self.z * k)
+    }
+}
+
+impl Div<f32> for Vec3d {
+    type Output = Vec3d;
+
+    fn div(self, k: f32) -> Vec3d {
+        Vec3d::new(self.x / k, self.y / k, self.z / k)
+    }
+}
+
+/// Transform a point by an affine matrix, as `Vec3d::mult_mat`
+impl Mul<Mat4x4> for Vec3d {
+    type Output = Vec3d;
+
+    fn mul(self, mat: Mat4x4) -> Vec3d {
+        Vec3d::mult_mat(&self, &mat)
+    }
+}
+
+impl Neg for Vec3d {
+    type Output = Vec3d;
+
+    fn neg(self) -> Vec3d {
+        Vec3d::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl AddAssign for Vec3d {
+    fn add_assign(&mut self, rhs: Vec3d) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Vec3d {
+    fn sub_assign(&mut self, rhs: Vec3d) {
+        *self = *self - rhs;
+    }
+}
+
+impl MulAssign<f32> for Vec3d {
+    fn mul_assign(&mut self, k: f32) {
+        *self = *self * k;
+    }
+}
+
+impl DivAssign<f32> for Vec3d {
+    fn div_assign(&mut self, k: f32) {
+        *self = *self / k;
+    }
+}
+
+// 4 dimensional homogeneous vector. A point has `w` = 1 until it is projected
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Vec4d {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Vec4d {
+    /// Construct a new 4D vector
+    pub fn new(x: f32, y: f32, z: f32, w: f32) -> Vec4d {
+        Vec4d { x, y, z, w }
+    }
+
+    /// Construct the homogeneous form of a point, with `w` = 1
+    pub fn from_point(v: &Vec3d) -> Vec4d {
+        Vec4d::new(v.x, v.y, v.z, 1.0)
+    }
+
+    /// Construct the plane through `p` with normal `n`, such that its dot product with a point (with `w` = 1) is the point's
+    /// distance in front of the plane
+    pub fn plane(p: &Vec3d, n: &Vec3d) -> Vec4d {
+        let n = n.normalise();
+        Vec4d::new(n.x, n.y, n.z, -Vec3d::dot_product(&n, p))
+    }
+
+    /// Return the dot product between two vectors
+    pub fn dot_product(v1: &Vec4d, v2: &Vec4d) -> f32 {
+        v1.x * v2.x + v1.y * v2.y + v1.z * v2.z + v1.w * v2.w
+    }
+
+    /// Perspective divide: return the 3D point this represents, dividing each component by `w`
+    pub fn divide(&self) -> Vec3d {
+        Vec3d::new(self.x / self.w, self.y / self.w, self.z / self.w)
+    }
+}
+
+impl Add for Vec4d {
+    type Output = Vec4d;
+
+    fn add(self, rhs: Vec4d) -> Vec4d {
+        Vec4d::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z, self.w + rhs.w)
+    }
+}
+
+impl Mul<f32> for Vec4d {
+    type Output = Vec4d;
+
+    fn mul(self, k: f32) -> Vec4d {
+        Vec4d::new(self.x * k, self.y * k, self.z * k, self.w * k)
+    }
+}
+
+/// Transform by any matrix, including projections. Nothing is divided by `w`
+impl Mul<Mat4x4> for Vec4d {
+    type Output = Vec4d;
+
+    fn mul(self, mat: Mat4x4) -> Vec4d {
+        Vec4d::new(
+            self.x * mat.0.0 + self.y * mat.1.0 + self.z * mat.2.0 + self.w * mat.3.0,
+            self.x * mat.0.1 + self.y * mat.1.1 + self.z * mat.2.1 + self.w * mat.3.1,
+            self.x * mat.0.2 + self.y * mat.1.2 + self.z * mat.2.2 + self.w * mat.3.2,
+            self.x * mat.0.3 + self.y * mat.1.3 + self.z * mat.2.3 + self.w * mat.3.3,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operators() {
+        let a = Vec3d::new(1.0, 2.0, 3.0);
+        let b = Vec3d::new(4.0, -1.0, 0.5);
+        assert_eq!(a + b, Vec3d::new(5.0, 1.0, 3.5));
+        assert_eq!(a - b, Vec3d::new(-3.0, 3.0, 2.5));
+        assert_eq!(a * 2.0, Vec3d::new(2.0, 4.0, 6.0));
+        assert_eq!(a / 2.0, Vec3d::new(0.5, 1.0, 1.5));
+        assert_eq!(-a, Vec3d::new(-1.0, -2.0, -3.0));
+
+        let mut c = a;
+        c += b;
+        assert_eq!(c, a + b);
+        c -= b;
+        assert_eq!(c, a);
+        c *= 4.0;
+        assert_eq!(c, a * 4.0);
+        c /= 4.0;
+        assert_eq!(c, a);
+    }
+
+    #[test]
+    fn matrix() {
+        let v = Vec3d::new(1.0, 2.0, 3.0);
+        assert_eq!(v * Mat4x4::translation(1.0, 1.0, 1.0), Vec3d::new(2.0, 3.0, 4.0));
+        let m = Mat4x4::rot_y(0.7) * Mat4x4::scale(2.0, 1.0, 1.0);
+        assert_eq!(v * m, Vec3d::mult_mat(&v, &m));
+        // Multiplying by two matrices in turn is the same as multiplying by their product
+        let t = Mat4x4::translation(0.0, -5.0, 2.0);
+        let (a, b) = ((v * m) * t, v * (m * t));
+        assert!((a - b).length() < 1e-5, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn homogeneous() {
+        let v = Vec4d::from_point(&Vec3d::new(1.0, 2.0, 3.0));
+        assert_eq!(v, Vec4d::new(1.0, 2.0, 3.0, 1.0));
+        // Affine matrices leave w alone, so agree with Vec3d
+        let m = Mat4x4::rot_x(0.3) * Mat4x4::translation(1.0, -1.0, 2.0);
+        let p = v * m;
+        assert_eq!(p.w, 1.0);
+        assert_eq!(p.divide(), Vec3d::new(1.0, 2.0, 3.0) * m);
+        // Projections are not divided until asked
+        let p = v * Mat4x4::perspective(std::f32::consts::FRAC_PI_2, 1.0, 1.0, 11.0);
+        assert!((p.w - 3.0).abs() < 1e-6);
+        let d = p.divide();
+        assert!((d.x - 1.0 / 3.0).abs() < 1e-6 && (d.y - 2.0 / 3.0).abs() < 1e-6, "{:?}", d);
+
+        let plane = Vec4d::plane(&Vec3d::new(0.0, 0.0, 1.0), &Vec3d::new(0.0, 0.0, 2.0));
+        assert_eq!(Vec4d::dot_product(&plane, &v), 2.0);
+        assert_eq!(v + v * 2.0, Vec4d::new(3.0, 6.0, 9.0, 3.0));
+    }
+}
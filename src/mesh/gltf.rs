@@ -0,0 +1,209 @@
+use std::path::Path;
+
+use colorsys::Rgb;
+
+use super::{FaceSettings, Mesh};
+use super::super::lighting::linear_to_srgb;
+use super::super::maths::quat::Quat;
+use super::super::maths::vec::Vec3d;
+use super::super::scene::{Node, Scene, Transform};
+
+/// Convert a glTF color, which is linear with each channel from 0 to 1, to the sRGB colors used by meshes
+fn srgb(r: f32, g: f32, b: f32) -> Rgb {
+    let channel = |c: f32| linear_to_srgb(c.clamp(0.0, 1.0)) as f64 * 255.0;
+    Rgb::new(channel(r), channel(g), channel(b), None)
+}
+
+/// Convert a triangle primitive to a mesh, styled by its material's base color. Return None for other kinds of primitive
+fn primitive_mesh(primitive: &::gltf::Primitive, buffers: &[::gltf::buffer::Data]) -> Option<Mesh> {
+    if primitive.mode() != ::gltf::mesh::Mode::Triangles {
+        return None;
+    }
+    let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+
+    let mut mesh = Mesh::new();
+    for p in reader.read_positions()? {
+        mesh.add_vertex_force(&Vec3d::new(p[0], p[1], p[2]));
+    }
+    if let Some(normals) = reader.read_normals() {
+        for (i, n) in normals.enumerate() {
+            mesh.set_vertex_normal(i, Some(Vec3d::new(n[0], n[1], n[2])));
+        }
+    }
+    if let Some(uvs) = reader.read_tex_coords(0) {
+        // glTF's `v` runs from the top of the image down
+        for (i, uv) in uvs.into_f32().enumerate() {
+            mesh.set_vertex_uv(i, Some((uv[0], 1.0 - uv[1])));
+        }
+    }
+    if let Some(colors) = reader.read_colors(0) {
+        for (i, c) in colors.into_rgb_f32().enumerate() {
+            mesh.set_vertex_color(i, Some(srgb(c[0], c[1], c[2])));
+        }
+    }
+
+    // Primitives without a material use the default, which is white
+    let [r, g, b, a] = primitive.material().pbr_metallic_roughness().base_color_factor();
+    let style = mesh.add_style(&FaceSettings {
+        fill: Some(srgb(r, g, b)),
+        opacity: a,
+        ..Default::default()
+    });
+
+    let indices: Vec<usize> = match reader.read_indices() {
+        Some(indices) => indices.into_u32().map(|i| i as usize).collect(),
+        None => (0..mesh.vertex_count()).collect(),
+    };
+    for tri in indices.chunks_exact(3) {
+        if tri.iter().all(|&i| i < mesh.vertex_count()) {
+            mesh.add_tri(tri[0], tri[1], tri[2], &Some(style));
+        }
+    }
+    Some(mesh)
+}
+
+/// Convert a glTF node's transform. glTF rotates column vectors, and `Quat` row vectors, so the rotation is inverted
+fn to_transform(transform: ::gltf::scene::Transform) -> Transform {
+    let (t, r, s) = transform.decomposed();
+    Transform {
+        translation: Vec3d::new(t[0], t[1], t[2]),
+        rotation: Quat::new(r[3], r[0], r[1], r[2]).conjugate(),
+        scale: Vec3d::new(s[0], s[1], s[2]),
+    }
+}
+
+/// Parse a glTF 2.0 file, either `.gltf` (with embedded or neighbouring buffers) or binary `.glb`, into a scene with a node for each glTF node
+/// and a mesh for each glTF mesh, combining its primitives. Only triangle primitives are loaded, and images are not, though texture
+/// coordinates are. Roots come from the default scene, or the first if there is no default
+pub fn parse_file(filename: String) -> Result<Scene, ::gltf::Error> {
+    let path = Path::new(&filename);
+    let bytes = std::fs::read(path).map_err(::gltf::Error::Io)?;
+    parse(&bytes, path.parent())
+}
+
+/// Parse the contents of a `.gltf` or `.glb` file. Buffers which are not embedded are read relative to `dir`
+fn parse(bytes: &[u8], dir: Option<&Path>) -> Result<Scene, ::gltf::Error> {
+    let ::gltf::Gltf { document, blob } = ::gltf::Gltf::from_slice(bytes)?;
+    let buffers = ::gltf::import_buffers(&document, dir, blob)?;
+
+    // Meshes and nodes keep their glTF indices, so children and meshes can be referred to as they are
+    let mut scene = Scene::new();
+    for gltf_mesh in document.meshes() {
+        let mut mesh = Mesh::new();
+        for primitive in gltf_mesh.primitives().filter_map(|primitive| primitive_mesh(&primitive, &buffers)) {
+            mesh.merge(&primitive);
+        }
+        scene.add_mesh(mesh);
+    }
+
+    scene.nodes = document
+        .nodes()
+        .map(|node| Node {
+            transform: to_transform(node.transform()),
+            mesh: node.mesh().map(|mesh| mesh.index()),
+            children: node.children().map(|child| child.index()).collect(),
+        })
+        .collect();
+
+    scene.roots = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+        .map(|gltf_scene| gltf_scene.nodes().map(|node| node.index()).collect())
+        .unwrap_or_default();
+
+    Ok(scene)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An orange, half transparent triangle instanced by two nodes under a translated parent. `{}` is replaced by the buffer
+    const JSON: &str = r#"{
+        "asset": { "version": "2.0" },
+        "buffers": [{ "byteLength": 44 {} }],
+        "bufferViews": [
+            { "buffer": 0, "byteOffset": 0, "byteLength": 36 },
+            { "buffer": 0, "byteOffset": 36, "byteLength": 6 }
+        ],
+        "accessors": [
+            { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [1, 1, 0] },
+            { "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }
+        ],
+        "materials": [{ "pbrMetallicRoughness": { "baseColorFactor": [1, 0.214041, 0, 0.5] } }],
+        "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 }, "indices": 1, "material": 0 }] }],
+        "nodes": [
+            { "translation": [0, 0, 5], "children": [1, 2] },
+            { "mesh": 0, "translation": [1, 0, 0] },
+            { "mesh": 0, "scale": [2, 2, 2] }
+        ],
+        "scenes": [{ "nodes": [0] }],
+        "scene": 0
+    }"#;
+    /// Positions (0, 0, 0), (1, 0, 0) and (0, 1, 0), then indices 0, 1, 2, padded to 44 bytes
+    const BUFFER: &str = "AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAABAAIAAAA=";
+
+    fn check(scene: &Scene) {
+        assert_eq!(scene.meshes.len(), 1);
+        assert_eq!(scene.nodes.len(), 3);
+        assert_eq!(scene.roots, vec![0]);
+        let style = &scene.meshes[0].settings[0];
+        let fill = style.fill.as_ref().unwrap();
+        // The factor is linear, so its green is half of full green once encoded as sRGB
+        let rgb = (fill.red(), fill.green(), fill.blue());
+        assert!((rgb.0 - 255.0).abs() < 1e-3 && (rgb.1 - 127.5).abs() < 1e-3 && rgb.2 == 0.0, "{:?}", rgb);
+        assert_eq!(style.opacity, 0.5);
+
+        let mesh = scene.flatten();
+        assert_eq!(mesh.face_count(), 2);
+        let (min, max) = mesh.bounds().unwrap();
+        assert!(min.equals(&Vec3d::new(0.0, 0.0, 5.0)), "{} {} {}", min.x, min.y, min.z);
+        assert!(max.equals(&Vec3d::new(2.0, 2.0, 5.0)), "{} {} {}", max.x, max.y, max.z);
+    }
+
+    #[test]
+    fn embedded() {
+        let json = JSON.replace("{}", &format!(r#", "uri": "data:application/octet-stream;base64,{}""#, BUFFER));
+        check(&parse(json.as_bytes(), None).unwrap());
+    }
+
+    #[test]
+    fn binary() {
+        let mut json = JSON.replace("{}", "").into_bytes();
+        while !json.len().is_multiple_of(4) {
+            json.push(b' ');
+        }
+        let mut bin = Vec::new();
+        for f in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+            bin.extend(f.to_le_bytes());
+        }
+        for i in [0u16, 1, 2, 0] {
+            bin.extend(i.to_le_bytes());
+        }
+
+        let mut glb = Vec::new();
+        glb.extend(b"glTF");
+        glb.extend(2u32.to_le_bytes());
+        glb.extend((12 + 8 + json.len() as u32 + 8 + bin.len() as u32).to_le_bytes());
+        glb.extend((json.len() as u32).to_le_bytes());
+        glb.extend(b"JSON");
+        glb.extend(json);
+        glb.extend((bin.len() as u32).to_le_bytes());
+        glb.extend(b"BIN\0");
+        glb.extend(bin);
+        check(&parse(&glb, None).unwrap());
+    }
+
+    #[test]
+    fn rotation() {
+        // A quarter turn around Z, which glTF applies anticlockwise looking down Z: X goes to Y
+        let json = JSON.replace("{}", &format!(r#", "uri": "data:application/octet-stream;base64,{}""#, BUFFER)).replace(
+            r#"{ "mesh": 0, "translation": [1, 0, 0] }"#,
+            r#"{ "mesh": 0, "rotation": [0, 0, 0.70710678, 0.70710678] }"#,
+        );
+        let scene = parse(json.as_bytes(), None).unwrap();
+        let world = scene.instances().into_iter().map(|(_, world)| world).find(|world| world.0.0.abs() < 1e-5).unwrap();
+        let v = Vec3d::mult_mat(&Vec3d::unit_x(), &world);
+        assert!(v.x.abs() < 1e-5 && (v.y - 1.0).abs() < 1e-5 && (v.z - 5.0).abs() < 1e-5, "{} {} {}", v.x, v.y, v.z);
+    }
+}
@@ -0,0 +1,470 @@
+pub mod gltf;
+pub mod mtl;
+pub mod obj;
+pub mod ply;
+pub mod shape;
+pub mod stl;
+
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use colorsys::Rgb;
+
+use crate::{Mat4x4, Vec3d, scene::Scene, lighting::{Intensity, Material}, texture::{Texture, Uv}, triangle::Triangle};
+
+pub struct Mesh {
+    vertices: Vec<Vec3d>,
+    colors: Vec<Option<Rgb>>, // Color of each vertex. Used in place of a face's fill, if every vertex of the face has one
+    normals: Vec<Option<Vec3d>>, // Unit normal of each vertex, used for smooth shading
+    uvs: Vec<Option<Uv>>, // Texture coordinates of each vertex. Used to apply a face's texture, if every vertex of the face has them
+    faces: Vec<(usize, usize, usize, Option<usize>)>, // Faces consist of three vectors describing a triangle, clockwise, and a faceSettings index
+    settings: Vec<FaceSettings>,
+    pub default_fill: Option<Rgb>,
+    pub default_stroke: Option<Rgb>,
+}
+
+impl Clone for Mesh {
+    fn clone(&self) -> Self {
+        Mesh {
+            vertices: self.vertices.clone(),
+            colors: self.colors.clone(),
+            normals: self.normals.clone(),
+            uvs: self.uvs.clone(),
+            faces: self.faces.clone(),
+            settings: self.settings.clone(),
+            default_fill: self.default_fill.clone(),
+            default_stroke: self.default_stroke.clone(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Mesh {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Mesh object ({} faces)", self.face_count())
+    }
+}
+
+/// How lighting is spread across a face
+#[derive(Clone, Copy, PartialEq)]
+pub enum Shading {
+    /// Light the face once, using its own normal
+    Flat,
+    /// Light each vertex using its normal, and blend across the face (Gouraud shading). Faces whose vertices lack normals are lit flat
+    Smooth,
+    /// Blend normals across the face, and light every pixel (Phong shading). Only the depth buffer does this; otherwise faces are lit smooth
+    PerPixel,
+}
+
+pub struct FaceSettings {
+    pub fill: Option<Rgb>,
+    pub stroke: Option<Rgb>,
+    /// Ambient color (`Ka`). Not used by the renderer, which reflects ambient light in the fill color; kept so it can be saved again
+    pub ambient: Option<Rgb>,
+    /// Specular highlight color. If nothing, no highlights
+    pub specular: Option<Rgb>,
+    /// Specular exponent. Higher values give smaller, sharper highlights
+    pub shininess: f32,
+    /// Opacity, from 0 (transparent) to 1 (opaque). Faces are always drawn opaque; this is kept so it can be saved again
+    pub opacity: f32,
+    /// Path of the image to use as the fill color
+    pub texture: Option<PathBuf>,
+    /// Image to use as the fill color, loaded from `texture`. Faces are only textured if their vertices have texture coordinates
+    pub image: Option<Rc<Texture>>,
+    /// How to shade the face. If nothing, the renderer decides
+    pub shading: Option<Shading>,
+}
+
+impl Clone for FaceSettings {
+    fn clone(&self) -> Self {
+        FaceSettings {
+            fill: self.fill.clone(),
+            stroke: self.stroke.clone(),
+            ambient: self.ambient.clone(),
+            specular: self.specular.clone(),
+            shininess: self.shininess,
+            opacity: self.opacity,
+            texture: self.texture.clone(),
+            image: self.image.clone(),
+            shading: self.shading,
+        }
+    }
+}
+
+impl Default for FaceSettings {
+    fn default() -> Self {
+        FaceSettings {
+            fill: None,
+            stroke: None,
+            ambient: None,
+            specular: None,
+            shininess: 0.0,
+            opacity: 1.0,
+            texture: None,
+            image: None,
+            shading: None,
+        }
+    }
+}
+
+impl Mesh {
+    pub fn new() -> Mesh {
+        Mesh {
+            vertices: Vec::new(),
+            colors: Vec::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            faces: Vec::new(),
+            settings: Vec::new(),
+            default_fill: Some(Rgb::new(255.0,255.0, 255.0, None)),
+            default_stroke: Some(Rgb::new(0.0,0.0, 0.0, None)),
+        }
+    }
+
+    /// Return index of a vertex (add vertex if not found)
+    pub fn add_vertex(&mut self, vertex: &Vec3d) -> usize {
+        let index = self.vertices.iter().position(|v| v.equals(&vertex));
+        if index.is_some() {
+            index.unwrap()
+        } else {
+            self.add_vertex_force(vertex)
+        }
+    }
+
+    /// Add a vertex, even if it exists already
+    pub fn add_vertex_force(&mut self, vertex: &Vec3d) -> usize {
+        self.vertices.push(*vertex);
+        self.colors.push(None);
+        self.normals.push(None);
+        self.uvs.push(None);
+        self.vertices.len() - 1
+    }
+
+    /// Add a copy of the given vertex, with its color, normal and texture coordinates, returning its index
+    pub fn duplicate_vertex(&mut self, i: usize) -> usize {
+        self.vertices.push(self.vertices[i]);
+        self.colors.push(self.colors[i].clone());
+        self.normals.push(self.normals[i]);
+        self.uvs.push(self.uvs[i]);
+        self.vertices.len() - 1
+    }
+
+    /// Set the color of the given vertex. If nothing, faces using it are filled with their style instead
+    pub fn set_vertex_color(&mut self, i: usize, color: Option<Rgb>) {
+        self.colors[i] = color;
+    }
+
+    /// Set the normal of the given vertex, which is normalised. If nothing, faces using it are lit flat
+    pub fn set_vertex_normal(&mut self, i: usize, normal: Option<Vec3d>) {
+        self.normals[i] = normal.map(|n| n.normalise());
+    }
+
+    /// Set the texture coordinates `(u, v)` of the given vertex. If nothing, faces using it are not textured
+    pub fn set_vertex_uv(&mut self, i: usize, uv: Option<Uv>) {
+        self.uvs[i] = uv;
+    }
+
+    /// Give every vertex without a normal the average normal of the faces using it, weighted by their area
+    pub fn compute_normals(&mut self) {
+        let mut sums = vec![Vec3d::origin(); self.vertices.len()];
+        for face in &self.faces {
+            let (a, b, c) = (&self.vertices[face.0], &self.vertices[face.1], &self.vertices[face.2]);
+            // The cross product's length is twice the face's area
+            let normal = Vec3d::normal(&(*b - *a), &(*c - *a));
+            for i in [face.0, face.1, face.2] {
+                sums[i] += normal;
+            }
+        }
+        for (normal, sum) in self.normals.iter_mut().zip(sums) {
+            if normal.is_none() && sum.length() > 0.0 {
+                *normal = Some(sum.normalise());
+            }
+        }
+    }
+
+    /// Add a new style
+    pub fn add_style(&mut self, style: &FaceSettings) -> usize {
+        self.settings.push(style.clone());
+        self.settings.len() - 1
+    }
+
+    /// Remove all styles
+    pub fn clear_styles(&mut self) {
+        self.settings.clear();
+        for i in 0..self.faces.len() {
+            self.faces[i].3 = None;
+        }
+    }
+
+    /// Set faceSettings index of every face
+    pub fn set_global_style(&mut self, style: Option<usize>) {
+        for i in 0..self.faces.len() {
+            self.faces[i].3 = style.clone();
+        }
+    }
+
+    /// Add a triangle (even if it exists, add a new one)
+    pub fn add_tri(&mut self, a: usize, b: usize, c: usize, style: &Option<usize>) -> &mut Self {
+        let face = (a, b, c, style.clone());
+        self.faces.push(face);
+        self
+    }
+
+    /// Add a quadrilateral (even if it exists, add a new one). Start hottom-letf clockwise
+    pub fn add_quad(&mut self, a: usize, b: usize, c: usize, d: usize, style: &Option<usize>) -> &mut Self {
+        self.faces.push((a, b, c, style.clone()));
+        self.faces.push((a, c, d, style.clone()));
+        self
+    }
+
+    /// Get the given Vertex
+    pub fn compile_vertex(&mut self, i: usize) -> Option<Vec3d> {
+        if i < self.vertices.len() {
+            Some(self.vertices[i])
+        } else {
+            None
+        }
+    }
+
+    /// Count vertices
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
+
+    /// Count faces
+    pub fn face_count(&self) -> usize {
+        self.faces.len()
+    }
+
+    /// Is this mesh empty?
+    pub fn is_empty(&self) -> bool {
+        self.faces.len() == 0
+    }
+
+    /// Get the minimum and maximum corners of the box containing every vertex. Return None if there are no vertices
+    pub fn bounds(&self) -> Option<(Vec3d, Vec3d)> {
+        let first = self.vertices.first()?;
+        let mut min = *first;
+        let mut max = *first;
+        for v in &self.vertices {
+            min = Vec3d::new(min.x.min(v.x), min.y.min(v.y), min.z.min(v.z));
+            max = Vec3d::new(max.x.max(v.x), max.y.max(v.y), max.z.max(v.z));
+        }
+        Some((min, max))
+    }
+
+    /// Get the given face
+    pub fn compile_face(&mut self, i: usize) -> Option<Triangle> {
+        if i < self.faces.len() {
+            let face = self.faces[i];
+            let mut tri = Triangle::new(
+                self.vertices[face.0],
+                self.vertices[face.1],
+                self.vertices[face.2],
+            );
+            if face.3.is_some() {
+                let settings = &self.settings[face.3.unwrap()];
+                tri.fill = settings.fill.clone();
+                tri.stroke = settings.stroke.clone();
+                tri.shading = settings.shading;
+                tri.texture = settings.image.clone();
+                tri.material = Material {
+                    specular: settings.specular.as_ref().map_or(Intensity::grey(0.0), |rgb| Intensity::from_rgb(rgb, 1.0)),
+                    shininess: settings.shininess,
+                };
+            } else {
+                tri.fill = self.default_fill.clone();
+                tri.stroke = self.default_stroke.clone();
+            }
+            if let (Some(a), Some(b), Some(c)) = (&self.colors[face.0], &self.colors[face.1], &self.colors[face.2]) {
+                tri.colors = Some((a.clone(), b.clone(), c.clone()));
+            }
+            if let (Some(a), Some(b), Some(c)) = (&self.normals[face.0], &self.normals[face.1], &self.normals[face.2]) {
+                tri.normals = Some((*a, *b, *c));
+            }
+            if let (Some(a), Some(b), Some(c)) = (self.uvs[face.0], self.uvs[face.1], self.uvs[face.2]) {
+                tri.uvs = Some((a, b, c));
+            }
+            Some(tri)
+        } else {
+            None
+        }
+    }
+
+    /// Merge another mesh builder. Mutate current builder, and return self. Doesn't mutate other builder.
+    pub fn merge(&mut self, other: &Mesh) -> &mut Self {
+        if other.is_empty() {
+            return self;
+        }
+
+        let vertices_start = self.vertices.len();
+        let settings_start = self.settings.len();
+
+        // Copy over other's vertices
+        for i in 0..other.vertices.len() {
+            self.vertices.push(other.vertices[i]);
+            self.colors.push(other.colors[i].clone());
+            self.normals.push(other.normals[i]);
+            self.uvs.push(other.uvs[i]);
+        }
+
+        // Copy over other's stylings
+        if other.settings.len() > 0 {
+            for i in 0..other.settings.len() {
+                self.settings.push(other.settings[i].clone());
+            }
+        }
+
+        // Copy over default style?
+        let default_style = if other.default_fill.is_some() || other.default_stroke.is_some() {
+            let style = FaceSettings {
+                fill: other.default_fill.clone(),
+                stroke: other.default_stroke.clone(),
+                ..Default::default()
+            };
+            self.settings.push(style);
+            Some(self.settings.len() - 1)
+        } else {
+            None
+        };
+
+        // Copy other other's faces
+        for i in 0..other.faces.len() {
+            let face = &other.faces[i];
+            let new_face = (
+                vertices_start + face.0,
+                vertices_start + face.1,
+                vertices_start + face.2,
+                if face.3.is_some() {
+                    Some(settings_start + face.3.unwrap())
+                } else {
+                    default_style.clone()
+                }
+            );
+            self.faces.push(new_face);
+        }
+
+        self
+    }
+
+    /// Transform all vertices by a matrix. Normals are carried by its inverse transpose, so stay perpendicular to the surface under
+    /// non-uniform scaling. A mirroring matrix would turn faces inside out, so their winding is reversed to keep them facing outwards
+    pub fn transform(&mut self, mat: &Mat4x4) -> &mut Self {
+        let normal_matrix = mat.normal_matrix();
+        for i in 0..self.vertices.len() {
+            self.vertices[i] = self.vertices[i] * *mat;
+            if let Some(normal) = &self.normals[i] {
+                self.normals[i] = Some(Vec3d::mult_mat_dir(normal, &normal_matrix).normalise());
+            }
+        }
+        if mat.determinant() < 0.0 {
+            for face in &mut self.faces {
+                std::mem::swap(&mut face.1, &mut face.2);
+            }
+        }
+        self
+    }
+
+    /// Translate all vertices by another vector
+    pub fn translate(&mut self, v: &Vec3d) -> &mut Self {
+        for i in 0..self.vertices.len() {
+            self.vertices[i] += *v;
+        }
+        self
+    }
+
+    /// Translate all vertices by another vector
+    pub fn translatek(&mut self, k: f32) -> &mut Self {
+        for i in 0..self.vertices.len() {
+            self.vertices[i] = self.vertices[i].addk(k);
+        }
+        self
+    }
+
+    /// Scale every vector by another vector
+    pub fn scale(&mut self, v: &Vec3d) -> &mut Self {
+        for i in 0..self.vertices.len() {
+            self.vertices[i] = self.vertices[i].mul(v);
+            if let Some(normal) = &self.normals[i] {
+                self.normals[i] = Some(Vec3d::new(normal.x / v.x, normal.y / v.y, normal.z / v.z).normalise());
+            }
+        }
+        self
+    }
+
+    /// Scale every vector by a constant
+    pub fn scalek(&mut self, k: f32) -> &mut Self {
+        for i in 0..self.vertices.len() {
+            self.vertices[i] *= k;
+        }
+        self
+    }
+
+}
+
+/// Does the file have the given extension?
+fn has_extension(filename: &Path, ext: &str) -> bool {
+    filename.extension().is_some_and(|e| e.eq_ignore_ascii_case(ext))
+}
+
+/// Load a scene from an OBJ, STL, PLY or glTF file, choosing the format by extension. glTF files keep their node hierarchy;
+/// other formats give a scene with a single node drawing the mesh
+pub fn load_file(filename: &str) -> Result<Scene, String> {
+    let path = Path::new(filename);
+    let mesh = if has_extension(path, "stl") {
+        stl::parse_file(filename.to_string()).map_err(|e| e.to_string())
+    } else if has_extension(path, "gltf") || has_extension(path, "glb") {
+        return gltf::parse_file(filename.to_string()).map_err(|e| e.to_string());
+    } else if has_extension(path, "ply") {
+        ply::parse_file(filename.to_string()).map_err(|e| e.to_string())
+    } else {
+        obj::parse_file(filename.to_string()).map_err(|e| e.to_string())
+    };
+    mesh.map(Scene::with_mesh)
+}
+
+/// Save a model as STL or OBJ (with its materials alongside), choosing the format by extension. `ascii` chooses between ASCII and binary STL
+pub fn save_file(mesh: &Mesh, filename: &Path, ascii: bool) -> std::io::Result<()> {
+    if has_extension(filename, "stl") && ascii {
+        stl::write_ascii(mesh, filename.to_string_lossy().to_string())
+    } else if has_extension(filename, "stl") {
+        stl::write_binary(mesh, filename.to_string_lossy().to_string())
+    } else {
+        obj::write_obj(mesh, filename.to_string_lossy().to_string(), true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Get the normal implied by the winding of each face, and the sum of its vertex normals
+    fn face_normals(mesh: &Mesh) -> Vec<(Vec3d, Vec3d)> {
+        mesh.faces.iter().map(|&(a, b, c, _)| {
+            let (va, vb, vc) = (mesh.vertices[a], mesh.vertices[b], mesh.vertices[c]);
+            let normals = mesh.normals[a].unwrap() + mesh.normals[b].unwrap() + mesh.normals[c].unwrap();
+            (Vec3d::normal(&(vb - va), &(vc - va)), normals)
+        }).collect()
+    }
+
+    #[test]
+    fn transform_normals() {
+        // On a sphere stretched along x into an ellipsoid, the normal at (x, y, z) is along (x / 9, y, z)
+        let mut mesh = shape::sphere(1.0, 8, 6);
+        mesh.transform(&Mat4x4::scale(3.0, 1.0, 1.0));
+        for (v, normal) in mesh.vertices.iter().zip(&mesh.normals) {
+            let expected = Vec3d::new(v.x / 9.0, v.y, v.z).normalise();
+            let normal = normal.unwrap();
+            assert!(Vec3d::dot_product(&normal, &expected) > 1.0 - 1e-4, "({}, {}, {}) at ({}, {}, {})", normal.x, normal.y, normal.z, v.x, v.y, v.z);
+        }
+    }
+
+    #[test]
+    fn transform_mirrored() {
+        // Faces keep the same side facing along their vertex normals after mirroring
+        let mut mesh = shape::sphere(1.0, 8, 6);
+        let before: Vec<f32> = face_normals(&mesh).iter().map(|(face, vertex)| Vec3d::dot_product(face, vertex).signum()).collect();
+        mesh.transform(&Mat4x4::scale(-1.0, 1.0, 2.0));
+        let after: Vec<f32> = face_normals(&mesh).iter().map(|(face, vertex)| Vec3d::dot_product(face, vertex).signum()).collect();
+        assert_eq!(before, after);
+    }
+}
@@ -0,0 +1,108 @@
+use std::fs::File;
+use std::io::{self, BufRead};
+use std::path::Path;
+use std::rc::Rc;
+
+use colorsys::Rgb;
+
+use super::FaceSettings;
+use super::obj::{parse_f32, ObjError};
+use super::super::texture;
+
+/// Parse the next three components of a directive as an RGB color, each from 0 to 1
+fn parse_color<'a>(line: &str, n: usize, iter: &mut impl Iterator<Item = &'a str>) -> Result<Rgb, ObjError> {
+    let r = parse_f32(line, n, iter)? as f64;
+    let g = parse_f32(line, n, iter)? as f64;
+    let b = parse_f32(line, n, iter)? as f64;
+    Ok(Rgb::new(r * 255.0, g * 255.0, b * 255.0, None))
+}
+
+/// Parse a material library, returning each material's name and style. Texture paths are relative to the library.
+/// Textures are loaded if they are PNG images; otherwise, or if they cannot be read, faces are filled with `Kd` instead.
+/// Only `Kd`, `Ka`, `Ks`, `Ns`, `d`, `Tr` and `map_Kd` are read; other directives are ignored
+pub fn parse_file(filename: &Path) -> Result<Vec<(String, FaceSettings)>, ObjError> {
+    let file = File::open(filename)?;
+    let lines = io::BufReader::new(file).lines();
+    let dir = filename.parent().unwrap_or(Path::new(""));
+    let mut materials: Vec<(String, FaceSettings)> = Vec::new();
+
+    for (i, res) in lines.enumerate() {
+        let n = i + 1;
+        let line = res?;
+        let mut iter = line.split_whitespace();
+        let directive = iter.next();
+
+        if directive == Some("newmtl") {
+            let name = iter.next().ok_or(ObjError::MissingComponent { line: n, column: line.trim_end().len() + 1, text: line.clone() })?;
+            materials.push((name.to_string(), FaceSettings::default()));
+        } else if let (Some(directive), Some((_, style))) = (directive, materials.last_mut()) {
+            match directive {
+                "Kd" => style.fill = Some(parse_color(&line, n, &mut iter)?),
+                "Ka" => style.ambient = Some(parse_color(&line, n, &mut iter)?),
+                "Ks" => style.specular = Some(parse_color(&line, n, &mut iter)?),
+                "Ns" => style.shininess = parse_f32(&line, n, &mut iter)?,
+                "d" => style.opacity = parse_f32(&line, n, &mut iter)?,
+                "Tr" => style.opacity = 1.0 - parse_f32(&line, n, &mut iter)?,
+                "map_Kd" => {
+                    // Options may come before the file name, so take the last component
+                    let texture = iter.last().ok_or(ObjError::MissingComponent { line: n, column: line.trim_end().len() + 1, text: line.clone() })?;
+                    let path = dir.join(texture);
+                    style.image = texture::parse_file(path.to_string_lossy().to_string()).ok().map(Rc::new);
+                    style.texture = Some(path);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    Ok(materials)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempPath;
+
+    /// Write `text` to a uniquely named library in the temporary directory
+    fn write_library(name: &str, text: &str) -> TempPath {
+        TempPath::with_contents(&format!("mtl_{}.mtl", name), text)
+    }
+
+    fn assert_rgb(color: &Option<Rgb>, rgb: (f64, f64, f64)) {
+        let color = color.as_ref().expect("color is set");
+        assert!((color.red() - rgb.0).abs() < 1e-3 && (color.green() - rgb.1).abs() < 1e-3 && (color.blue() - rgb.2).abs() < 1e-3, "{:?} != {:?}", color, rgb);
+    }
+
+    #[test]
+    fn directives() {
+        let path = write_library("directives", "# A comment\nnewmtl shiny\nKd 1 0 0\nKa 0 0.2 0\nKs 0.5 0.5 1\nNs 64\nd 0.25\nmap_Kd -s 1 1 1 missing.png\n\nnewmtl clear\nTr 0.75\nillum 2\n");
+        let materials = parse_file(path.path()).unwrap();
+
+        assert_eq!(materials.len(), 2);
+        let (name, shiny) = &materials[0];
+        assert_eq!(name, "shiny");
+        assert_rgb(&shiny.fill, (255.0, 0.0, 0.0));
+        assert_rgb(&shiny.ambient, (0.0, 51.0, 0.0));
+        assert_rgb(&shiny.specular, (127.5, 127.5, 255.0));
+        assert_eq!(shiny.shininess, 64.0);
+        assert_eq!(shiny.opacity, 0.25);
+        // The texture cannot be read, so only its path is kept
+        assert_eq!(shiny.texture, Some(path.path().parent().unwrap().join("missing.png")));
+        assert!(shiny.image.is_none());
+
+        let (name, clear) = &materials[1];
+        assert_eq!(name, "clear");
+        assert!(clear.fill.is_none());
+        assert_eq!(clear.opacity, 0.25);
+    }
+
+    #[test]
+    fn malformed_line() {
+        let path = write_library("malformed", "newmtl a\nKd 1 1 1\nNs shiny\n");
+        match parse_file(path.path()) {
+            Err(ObjError::MalformedNumber { line, column, text }) => assert_eq!((line, column, text.as_str()), (3, 4, "shiny")),
+            Err(e) => panic!("unexpected error {}", e),
+            Ok(_) => panic!("parsed a malformed library"),
+        }
+    }
+}
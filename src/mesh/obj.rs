@@ -0,0 +1,510 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::Path;
+
+use super::{mtl, Mesh};
+use super::super::maths::vec::Vec3d;
+use super::super::texture::Uv;
+
+/// Directives which are understood, but whose data is not used
+const IGNORED: [&str; 5] = ["vp", "o", "g", "s", "l"];
+
+/// Error encountered while parsing an OBJ file. Line and column numbers start at 1
+#[derive(Debug)]
+pub enum ObjError {
+    /// The file could not be opened or read
+    Io(io::Error),
+    /// A number could not be parsed
+    MalformedNumber { line: usize, column: usize, text: String },
+    /// A directive is missing one of its components
+    MissingComponent { line: usize, column: usize, text: String },
+    /// A face refers to a vertex which has not been defined
+    IndexOutOfRange { line: usize, column: usize, text: String },
+    /// A directive which is not supported
+    UnsupportedDirective { line: usize, column: usize, text: String },
+    /// Error in a material library referenced by `mtllib`
+    Mtl { filename: String, error: Box<ObjError> },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObjError::Io(e) => write!(f, "Error: {}", e),
+            ObjError::MalformedNumber { line, column, text } => write!(f, "Error (line {}, column {}): malformed number '{}'", line, column, text),
+            ObjError::MissingComponent { line, column, text } => write!(f, "Error (line {}, column {}): missing component in '{}'", line, column, text),
+            ObjError::IndexOutOfRange { line, column, text } => write!(f, "Error (line {}, column {}): vertex index '{}' is out of range", line, column, text),
+            ObjError::UnsupportedDirective { line, column, text } => write!(f, "Error (line {}, column {}): unsupported directive '{}'", line, column, text),
+            ObjError::Mtl { filename, error } => write!(f, "In material library {}: {}", filename, error),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ObjError::Io(e) => Some(e),
+            ObjError::Mtl { error, .. } => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ObjError {
+    fn from(e: io::Error) -> Self {
+        ObjError::Io(e)
+    }
+}
+
+/// Get the column at which `token` (a slice of `line`) starts
+pub(super) fn column(line: &str, token: &str) -> usize {
+    token.as_ptr() as usize - line.as_ptr() as usize + 1
+}
+
+/// Parse a one-based index into a list of `count` items, returning it zero-based. Negative indices count back from the end of the list
+fn parse_reference(n: usize, col: usize, text: &str, count: usize) -> Result<usize, ObjError> {
+    let index = text.parse::<isize>().map_err(|_| ObjError::MalformedNumber { line: n, column: col, text: text.to_string() })?;
+
+    if index > 0 && index as usize <= count {
+        Ok(index as usize - 1)
+    } else if index < 0 && index.unsigned_abs() <= count {
+        Ok(count - index.unsigned_abs())
+    } else {
+        Err(ObjError::IndexOutOfRange { line: n, column: col, text: text.to_string() })
+    }
+}
+
+/// Parse the vertex, texture coordinate and normal indices out of a face element (`v`, `v/vt`, `v//vn` or `v/vt/vn`), returning them zero-based.
+/// Negative indices count back from the last vertex, texture coordinate or normal read so far
+fn parse_index(line: &str, n: usize, token: &str, counts: (usize, usize, usize)) -> Result<(usize, Option<usize>, Option<usize>), ObjError> {
+    let mut parts = token.split('/');
+    let col = column(line, token);
+    let v = parse_reference(n, col, parts.next().unwrap_or(""), counts.0)?;
+    let mut optional = |count| match parts.next() {
+        Some(text) if !text.is_empty() => parse_reference(n, col, text, count).map(Some),
+        _ => Ok(None),
+    };
+    let vt = optional(counts.1)?;
+    let vn = optional(counts.2)?;
+    Ok((v, vt, vn))
+}
+
+/// Parse the next component of a directive as a number
+pub(super) fn parse_f32<'a>(line: &str, n: usize, iter: &mut impl Iterator<Item = &'a str>) -> Result<f32, ObjError> {
+    let token = iter.next().ok_or_else(|| ObjError::MissingComponent { line: n, column: line.trim_end().len() + 1, text: line.to_string() })?;
+    token.parse::<f32>().map_err(|_| ObjError::MalformedNumber { line: n, column: column(line, token), text: token.to_string() })
+}
+
+/// Parse an OBJ file. Materials from `mtllib` libraries (relative to the file) are added as styles, and assigned to faces by `usemtl`.
+/// A library which is missing or malformed is reported on stderr and skipped
+pub fn parse_file(filename: String) -> Result<Mesh, ObjError> {
+    let file = File::open(&filename)?;
+    let dir = Path::new(&filename).parent().unwrap_or(Path::new("")).to_path_buf();
+    let lines = io::BufReader::new(file).lines();
+    let mut mesh = Mesh::new();
+    let mut materials: HashMap<String, usize> = HashMap::new();
+    let mut style: Option<usize> = None;
+    let mut uvs: Vec<Uv> = Vec::new();
+    let mut normals: Vec<Vec3d> = Vec::new();
+    // Mesh vertex added for each `v`, in the order they appear. Face indices refer to this list, not to the mesh, which also holds duplicates
+    let mut positions: Vec<usize> = Vec::new();
+    // Mesh vertex used for each combination of vertex, texture coordinate and normal indices. The first combination to use a `v` gets
+    // its own vertex, and later ones get duplicates of it, so each can have its own texture coordinates and normal
+    let mut corners: HashMap<(usize, Option<usize>, Option<usize>), usize> = HashMap::new();
+    let mut claimed: Vec<bool> = Vec::new();
+
+    for (i, res) in lines.enumerate() {
+        let n = i + 1;
+        let line = res?;
+        let mut iter = line.split_whitespace();
+        match iter.next() {
+            Some("v") => { // Vertex
+                let a = parse_f32(&line, n, &mut iter)?;
+                let b = parse_f32(&line, n, &mut iter)?;
+                let c = parse_f32(&line, n, &mut iter)?;
+                positions.push(mesh.add_vertex_force(&Vec3d::new(a, b, c)));
+                claimed.push(false);
+            }
+            Some("vt") => { // Texture coordinates. `v` may be left out, and a third coordinate is not used
+                let u = parse_f32(&line, n, &mut iter)?;
+                let v = if iter.clone().next().is_some() { parse_f32(&line, n, &mut iter)? } else { 0.0 };
+                uvs.push((u, v));
+            }
+            Some("vn") => { // Vertex normal
+                let a = parse_f32(&line, n, &mut iter)?;
+                let b = parse_f32(&line, n, &mut iter)?;
+                let c = parse_f32(&line, n, &mut iter)?;
+                normals.push(Vec3d::new(a, b, c));
+            }
+            Some("f") => { // Face, which may be any polygon
+                let mut indices = Vec::new();
+                for token in iter {
+                    let (v, vt, vn) = parse_index(&line, n, token, (positions.len(), uvs.len(), normals.len()))?;
+                    let index = *corners.entry((v, vt, vn)).or_insert_with(|| {
+                        let index = if claimed[v] { mesh.duplicate_vertex(positions[v]) } else { positions[v] };
+                        claimed[v] = true;
+                        mesh.set_vertex_uv(index, vt.map(|vt| uvs[vt]));
+                        mesh.set_vertex_normal(index, vn.map(|vn| normals[vn]));
+                        index
+                    });
+                    indices.push(index);
+                }
+                if indices.len() < 3 {
+                    return Err(ObjError::MissingComponent { line: n, column: line.trim_end().len() + 1, text: line.clone() });
+                }
+
+                // Triangulate as a fan around the first vertex
+                for i in 1..indices.len() - 1 {
+                    mesh.add_tri(indices[0], indices[i], indices[i + 1], &style);
+                }
+            }
+            Some("mtllib") => { // Material libraries. One which cannot be loaded is skipped, so faces using its materials get the default style
+                for lib in iter {
+                    let path = dir.join(lib);
+                    match mtl::parse_file(&path) {
+                        Ok(library) => {
+                            for (name, settings) in library {
+                                materials.insert(name, mesh.add_style(&settings));
+                            }
+                        }
+                        Err(error) => eprintln!("Warning: {}", ObjError::Mtl { filename: path.display().to_string(), error: Box::new(error) }),
+                    }
+                }
+            }
+            Some("usemtl") => { // Switch material. Unknown materials use the default style
+                style = iter.next().and_then(|name| materials.get(name).copied());
+            }
+            Some(directive) if directive.starts_with('#') || IGNORED.contains(&directive) => { // Comments, and data which is not used
+            }
+            Some(directive) => {
+                return Err(ObjError::UnsupportedDirective { line: n, column: column(&line, directive), text: directive.to_string() });
+            }
+            None => (),
+        }
+    }
+
+    Ok(mesh)
+}
+
+/// Name given to the material for each style when exporting
+fn material_name(style: usize) -> String {
+    format!("style_{}", style)
+}
+
+/// Write a mesh as an OBJ file. If `with_mtl`, styles are written as materials to an MTL file of the same name alongside it.
+/// Faces without a style use the material `default`, which is left undefined so it is read back as no style.
+/// Strokes and the mesh's default colors cannot be represented, so are not written
+pub fn write_obj(mesh: &Mesh, filename: String, with_mtl: bool) -> io::Result<()> {
+    let path = Path::new(&filename);
+    let mut w = BufWriter::new(File::create(path)?);
+    writeln!(w, "# {} vertices, {} faces", mesh.vertices.len(), mesh.faces.len())?;
+
+    let with_mtl = with_mtl && !mesh.settings.is_empty();
+    if with_mtl {
+        let mtl_path = path.with_extension("mtl");
+        write_mtl(mesh, &mtl_path)?;
+        writeln!(w, "mtllib {}", mtl_path.file_name().unwrap().to_string_lossy())?;
+    }
+
+    for v in &mesh.vertices {
+        writeln!(w, "v {} {} {}", v.x, v.y, v.z)?;
+    }
+
+    // Texture coordinates and normals are only written if every vertex has them, so each vertex can share its index with them
+    let with_uvs = mesh.uvs.iter().all(|uv| uv.is_some());
+    if with_uvs {
+        for (u, v) in mesh.uvs.iter().flatten() {
+            writeln!(w, "vt {} {}", u, v)?;
+        }
+    }
+    let with_normals = mesh.normals.iter().all(|normal| normal.is_some());
+    if with_normals {
+        for normal in mesh.normals.iter().flatten() {
+            writeln!(w, "vn {} {} {}", normal.x, normal.y, normal.z)?;
+        }
+    }
+    let corner = |i: usize| match (with_uvs, with_normals) {
+        (true, true) => format!("{0}/{0}/{0}", i + 1),
+        (true, false) => format!("{0}/{0}", i + 1),
+        (false, true) => format!("{0}//{0}", i + 1),
+        (false, false) => format!("{}", i + 1),
+    };
+
+    let mut style: Option<usize> = None;
+    for (i, face) in mesh.faces.iter().enumerate() {
+        if with_mtl && (i == 0 || face.3 != style) {
+            style = face.3;
+            let name = style.map_or("default".to_string(), material_name);
+            writeln!(w, "usemtl {}", name)?;
+        }
+        writeln!(w, "f {} {} {}", corner(face.0), corner(face.1), corner(face.2))?;
+    }
+
+    w.flush()
+}
+
+/// Write each style of a mesh as a material. Texture paths are written relative to the MTL file where possible
+fn write_mtl(mesh: &Mesh, path: &Path) -> io::Result<()> {
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let mut w = BufWriter::new(File::create(path)?);
+    let color = |rgb: &colorsys::Rgb| format!("{} {} {}", rgb.red() / 255.0, rgb.green() / 255.0, rgb.blue() / 255.0);
+
+    for (i, style) in mesh.settings.iter().enumerate() {
+        writeln!(w, "newmtl {}", material_name(i))?;
+        if let Some(rgb) = &style.fill {
+            writeln!(w, "Kd {}", color(rgb))?;
+        }
+        if let Some(rgb) = &style.ambient {
+            writeln!(w, "Ka {}", color(rgb))?;
+        }
+        if let Some(rgb) = &style.specular {
+            writeln!(w, "Ks {}", color(rgb))?;
+        }
+        writeln!(w, "Ns {}", style.shininess)?;
+        writeln!(w, "d {}", style.opacity)?;
+        if let Some(texture) = &style.texture {
+            writeln!(w, "map_Kd {}", texture.strip_prefix(dir).unwrap_or(texture).display())?;
+        }
+        writeln!(w)?;
+    }
+
+    w.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use colorsys::Rgb;
+
+    use super::*;
+    use crate::mesh::{shape, FaceSettings};
+    use crate::test_util::TempPath;
+
+    fn assert_color(a: &Option<Rgb>, b: &Option<Rgb>) {
+        match (a, b) {
+            (Some(a), Some(b)) => {
+                assert!((a.red() - b.red()).abs() < 1e-3 && (a.green() - b.green()).abs() < 1e-3 && (a.blue() - b.blue()).abs() < 1e-3, "{:?} != {:?}", a, b);
+            }
+            (None, None) => (),
+            _ => panic!("{:?} != {:?}", a, b),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let temp = TempPath::new("obj_round_trip");
+        let dir = temp.path();
+        std::fs::create_dir_all(dir).unwrap();
+        let path = dir.join("cube.obj");
+
+        let mut mesh = shape::cuboid(&Vec3d::new(1.0, 2.5, -0.3));
+        let red = mesh.add_style(&FaceSettings {
+            fill: Some(Rgb::new(255.0, 0.0, 0.0, None)),
+            ambient: Some(Rgb::new(10.0, 20.0, 30.0, None)),
+            specular: Some(Rgb::new(255.0, 255.0, 255.0, None)),
+            shininess: 96.0,
+            ..Default::default()
+        });
+        let textured = mesh.add_style(&FaceSettings {
+            fill: Some(Rgb::new(128.0, 64.0, 1.0, None)),
+            opacity: 0.5,
+            texture: Some(dir.join("texture.png")),
+            ..Default::default()
+        });
+        for i in 0..4 {
+            mesh.faces[i].3 = Some(red);
+        }
+        for i in 6..8 {
+            mesh.faces[i].3 = Some(textured);
+        }
+
+        write_obj(&mesh, path.to_string_lossy().to_string(), true).unwrap();
+        let read = parse_file(path.to_string_lossy().to_string()).unwrap();
+
+        assert_eq!(read.vertices.len(), mesh.vertices.len());
+        for (a, b) in read.vertices.iter().zip(&mesh.vertices) {
+            assert!(a.equals(b));
+        }
+        assert_eq!(read.faces, mesh.faces);
+        assert_eq!(read.settings.len(), mesh.settings.len());
+        for (a, b) in read.settings.iter().zip(&mesh.settings) {
+            assert_color(&a.fill, &b.fill);
+            assert_color(&a.ambient, &b.ambient);
+            assert_color(&a.specular, &b.specular);
+            assert_eq!(a.shininess, b.shininess);
+            assert_eq!(a.opacity, b.opacity);
+            assert_eq!(a.texture, b.texture);
+        }
+    }
+
+    #[test]
+    fn round_trip_without_mtl() {
+        let path = TempPath::new("obj_round_trip.obj");
+        let mut mesh = shape::sphere(1.0, 8, 6);
+        mesh.add_style(&FaceSettings::default());
+        mesh.set_global_style(Some(0));
+
+        write_obj(&mesh, path.name(), false).unwrap();
+        let read = parse_file(path.name()).unwrap();
+
+        assert!(!path.path().with_extension("mtl").exists());
+        assert_eq!(read.vertices.len(), mesh.vertices.len());
+        assert_eq!(read.face_count(), mesh.face_count());
+        assert!(read.faces.iter().all(|f| f.3.is_none()));
+        for (a, b) in read.normals.iter().zip(&mesh.normals) {
+            assert!(a.as_ref().unwrap().equals(b.as_ref().unwrap()));
+        }
+    }
+
+    #[test]
+    fn vertex_normals() {
+        // Two faces share an edge, but only agree on the normal at one end of it
+        let read = parse_text("normals", "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nvn 0 0 2\nvn 0 1 0\nf 1//1 2//1 3//1\nf 2//1 4//1 3//2\n").unwrap();
+
+        assert_eq!(read.vertices.len(), 5);
+        assert_eq!(read.faces[0], (0, 1, 2, None));
+        assert_eq!(read.faces[1], (1, 3, 4, None));
+        assert!(read.vertices[4].equals(&read.vertices[2]));
+        assert!(read.normals[1].as_ref().unwrap().equals(&Vec3d::unit_z()));
+        assert!(read.normals[4].as_ref().unwrap().equals(&Vec3d::unit_y()));
+    }
+
+    #[test]
+    fn texture_coordinates() {
+        // The second face gives vertex 2 different texture coordinates, and leaves out `v` of the last coordinates
+        let path = TempPath::with_contents("obj_uvs.obj", "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nvt 0 0\nvt 1 0\nvt 0 1\nvt 0.5\nf 1/1 2/2 3/3\nf 2/4 4/2 3/3\n");
+        let read = parse_file(path.name()).unwrap();
+
+        assert_eq!(read.vertices.len(), 5);
+        assert_eq!(read.faces[0], (0, 1, 2, None));
+        assert_eq!(read.faces[1], (4, 3, 2, None));
+        assert_eq!(read.uvs, vec![Some((0.0, 0.0)), Some((1.0, 0.0)), Some((0.0, 1.0)), Some((1.0, 0.0)), Some((0.5, 0.0))]);
+        assert!(read.normals.iter().all(|normal| normal.is_none()));
+
+        write_obj(&read, path.name(), false).unwrap();
+        let written = std::fs::read_to_string(path.path()).unwrap();
+        let again = parse_file(path.name()).unwrap();
+        assert!(written.contains("f 1/1 2/2 3/3"));
+        assert_eq!(again.faces, read.faces);
+        assert_eq!(again.uvs, read.uvs);
+    }
+
+    #[test]
+    fn vertices_after_split() {
+        // Vertex 1 is split by the second face, before vertices 4 and 5 are read, so they must not be shifted by the duplicate
+        let read = parse_text("split", "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvt 1 1\nf 1/1 2/1 3/1\nf 1/2 3/2 2/2\nv 5 0 0\nv 0 5 0\nf 1 4 5\n").unwrap();
+
+        let face = read.faces[2];
+        assert_eq!(read.vertices[face.1], Vec3d::new(5.0, 0.0, 0.0));
+        assert_eq!(read.vertices[face.2], Vec3d::new(0.0, 5.0, 0.0));
+        assert_eq!(read.vertices[face.0], Vec3d::origin());
+        // Each distinct corner has its own vertex
+        assert_eq!(read.vertices.len(), 9);
+        assert_eq!(read.uvs[read.faces[0].0], Some((0.0, 0.0)));
+        assert_eq!(read.uvs[read.faces[1].0], Some((1.0, 1.0)));
+        assert_eq!(read.uvs[face.0], None);
+    }
+
+    #[test]
+    fn negative_indices() {
+        // Negative indices count back from the last `v`, `vt` or `vn` read, whatever vertices were split before
+        let text = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvn 0 0 1\nf -3/-1/-1 -2/-1/-1 -1/-1/-1\n\
+                    vt 1 1\nvn 0 1 0\nf -3/-1/-1 -1/-1/-1 -2/-1/-1\nv 2 2 2\nf -4 -3 -1\n";
+        let read = parse_text("negative", text).unwrap();
+
+        assert_eq!(read.faces.len(), 3);
+        let (a, b, c, _) = read.faces[1];
+        assert_eq!((read.vertices[a], read.vertices[b], read.vertices[c]), (Vec3d::new(0.0, 0.0, 0.0), Vec3d::new(0.0, 1.0, 0.0), Vec3d::new(1.0, 0.0, 0.0)));
+        assert_eq!(read.normals[a], Some(Vec3d::unit_y()));
+        assert_eq!(read.uvs[a], Some((1.0, 1.0)));
+        let (a, b, c, _) = read.faces[2];
+        assert_eq!((read.vertices[a], read.vertices[b], read.vertices[c]), (Vec3d::new(0.0, 0.0, 0.0), Vec3d::new(1.0, 0.0, 0.0), Vec3d::new(2.0, 2.0, 2.0)));
+    }
+
+    #[test]
+    fn missing_mtllib() {
+        let read = parse_text("missing_mtl", "mtllib does_not_exist.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl red\nf 1 2 3\n").unwrap();
+
+        assert_eq!(read.faces, vec![(0, 1, 2, None)]);
+        assert!(read.settings.is_empty());
+    }
+
+    /// Parse `text` as the contents of an OBJ file
+    fn parse_text(name: &str, text: &str) -> Result<Mesh, ObjError> {
+        parse_file(TempPath::with_contents(&format!("obj_{}.obj", name), text).name())
+    }
+
+    /// Parse `text`, which should fail, returning the error
+    fn parse_error(name: &str, text: &str) -> ObjError {
+        match parse_text(name, text) {
+            Ok(_) => panic!("parsed malformed file '{}'", text),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn face_forms() {
+        const VERTICES: &str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0.25 0.75\nvn 0 0 1\n";
+        let uv = Some((0.25, 0.75));
+        let normal = Some(Vec3d::unit_z());
+
+        let read = parse_text("form_v", &format!("{}f 1 2 3\n", VERTICES)).unwrap();
+        assert_eq!(read.faces, vec![(0, 1, 2, None)]);
+        assert!(read.uvs.iter().all(|uv| uv.is_none()) && read.normals.iter().all(|n| n.is_none()));
+
+        let read = parse_text("form_v_vt", &format!("{}f 1/1 2/1 3/1\n", VERTICES)).unwrap();
+        assert_eq!(read.faces, vec![(0, 1, 2, None)]);
+        assert_eq!(read.uvs, vec![uv; 3]);
+        assert!(read.normals.iter().all(|n| n.is_none()));
+
+        let read = parse_text("form_v_vn", &format!("{}f 1//1 2//1 3//1\n", VERTICES)).unwrap();
+        assert_eq!(read.faces, vec![(0, 1, 2, None)]);
+        assert!(read.uvs.iter().all(|uv| uv.is_none()));
+        assert_eq!(read.normals, vec![normal; 3]);
+
+        let read = parse_text("form_v_vt_vn", &format!("{}f 1/1/1 2/1/1 3/1/1\n", VERTICES)).unwrap();
+        assert_eq!(read.faces, vec![(0, 1, 2, None)]);
+        assert_eq!(read.uvs, vec![uv; 3]);
+        assert_eq!(read.normals, vec![normal; 3]);
+    }
+
+    #[test]
+    fn polygon_fan() {
+        let read = parse_text("fan", "v 0 0 0\nv 2 0 0\nv 3 1 0\nv 1 2 0\nv -1 1 0\nf 1 2 3 4 5\n").unwrap();
+        assert_eq!(read.faces, vec![(0, 1, 2, None), (0, 2, 3, None), (0, 3, 4, None)]);
+    }
+
+    #[test]
+    fn error_positions() {
+        assert!(matches!(parse_file("does_not_exist.obj".to_string()), Err(ObjError::Io(_))));
+
+        match parse_error("malformed", "# Comment\nv 0 x 0\n") {
+            ObjError::MalformedNumber { line, column, text } => assert_eq!((line, column, text.as_str()), (2, 5, "x")),
+            e => panic!("unexpected error {}", e),
+        }
+        match parse_error("malformed_index", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2/a 3\n") {
+            ObjError::MalformedNumber { line, column, text } => assert_eq!((line, column, text.as_str()), (4, 5, "a")),
+            e => panic!("unexpected error {}", e),
+        }
+        match parse_error("missing", "v 0 0 0\nvn 0 1\n") {
+            ObjError::MissingComponent { line, column, .. } => assert_eq!((line, column), (2, 7)),
+            e => panic!("unexpected error {}", e),
+        }
+        match parse_error("short_face", "v 0 0 0\nv 1 0 0\nf 1 2\n") {
+            ObjError::MissingComponent { line, column, .. } => assert_eq!((line, column), (3, 6)),
+            e => panic!("unexpected error {}", e),
+        }
+        match parse_error("out_of_range", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\n") {
+            ObjError::IndexOutOfRange { line, column, text } => assert_eq!((line, column, text.as_str()), (4, 7, "4")),
+            e => panic!("unexpected error {}", e),
+        }
+        match parse_error("out_of_range_vn", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 -3//1\n") {
+            ObjError::IndexOutOfRange { line, column, text } => assert_eq!((line, column, text.as_str()), (4, 7, "1")),
+            e => panic!("unexpected error {}", e),
+        }
+        match parse_error("unsupported", "v 0 0 0\n  curv 0 1 1 2\n") {
+            ObjError::UnsupportedDirective { line, column, text } => assert_eq!((line, column, text.as_str()), (2, 3, "curv")),
+            e => panic!("unexpected error {}", e),
+        }
+    }
+}
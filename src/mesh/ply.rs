@@ -0,0 +1,357 @@
+use std::fmt;
+use std::fs;
+use std::io;
+
+use colorsys::Rgb;
+
+use super::Mesh;
+use super::super::maths::vec::Vec3d;
+
+/// Error encountered while parsing a PLY file. Line numbers start at 1, element indices at 0
+#[derive(Debug)]
+pub enum PlyError {
+    /// The file could not be opened or read
+    Io(io::Error),
+    /// A header line is malformed or not understood
+    Header { line: usize, text: String },
+    /// A value in the body could not be parsed (ASCII only)
+    MalformedNumber { element: String, index: usize, text: String },
+    /// The file ended before every element was read
+    Truncated { element: String, index: usize },
+    /// A face refers to a vertex which does not exist
+    IndexOutOfRange { face: usize, index: i64 },
+}
+
+impl fmt::Display for PlyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlyError::Io(e) => write!(f, "Error: {}", e),
+            PlyError::Header { line, text } => write!(f, "Error (line {}): invalid header line '{}'", line, text),
+            PlyError::MalformedNumber { element, index, text } => write!(f, "Error ({} {}): malformed number '{}'", element, index, text),
+            PlyError::Truncated { element, index } => write!(f, "Error ({} {}): unexpected end of file", element, index),
+            PlyError::IndexOutOfRange { face, index } => write!(f, "Error (face {}): vertex index {} out of range", face, index),
+        }
+    }
+}
+
+impl std::error::Error for PlyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PlyError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PlyError {
+    fn from(e: io::Error) -> Self {
+        PlyError::Io(e)
+    }
+}
+
+/// Scalar type of a property
+#[derive(Clone, Copy, PartialEq)]
+enum Type {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Type {
+    fn parse(name: &str) -> Option<Type> {
+        match name {
+            "char" | "int8" => Some(Type::I8),
+            "uchar" | "uint8" => Some(Type::U8),
+            "short" | "int16" => Some(Type::I16),
+            "ushort" | "uint16" => Some(Type::U16),
+            "int" | "int32" => Some(Type::I32),
+            "uint" | "uint32" => Some(Type::U32),
+            "float" | "float32" => Some(Type::F32),
+            "double" | "float64" => Some(Type::F64),
+            _ => None,
+        }
+    }
+
+    /// Size in bytes, when stored in binary
+    fn size(self) -> usize {
+        match self {
+            Type::I8 | Type::U8 => 1,
+            Type::I16 | Type::U16 => 2,
+            Type::I32 | Type::U32 | Type::F32 => 4,
+            Type::F64 => 8,
+        }
+    }
+
+    fn is_float(self) -> bool {
+        self == Type::F32 || self == Type::F64
+    }
+
+    /// Decode a binary value. `bytes` must be exactly `size()` long
+    fn decode(self, bytes: &[u8], big_endian: bool) -> f64 {
+        let mut b = [0u8; 8];
+        b[..bytes.len()].copy_from_slice(bytes);
+        if big_endian {
+            b[..bytes.len()].reverse();
+        }
+        match self {
+            Type::I8 => b[0] as i8 as f64,
+            Type::U8 => b[0] as f64,
+            Type::I16 => i16::from_le_bytes([b[0], b[1]]) as f64,
+            Type::U16 => u16::from_le_bytes([b[0], b[1]]) as f64,
+            Type::I32 => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            Type::U32 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            Type::F32 => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            Type::F64 => f64::from_le_bytes(b),
+        }
+    }
+}
+
+/// A property of an element: either a single value, or a list of values preceded by their count
+enum Property {
+    Scalar(String, Type),
+    List(String, Type, Type),
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// Reads values from the body of the file
+enum Body<'a> {
+    Ascii(std::str::SplitAsciiWhitespace<'a>),
+    Binary { bytes: &'a [u8], big_endian: bool },
+}
+
+impl Body<'_> {
+    /// Read the next value. Return an error containing the offending text if it is malformed, or None at the end of the file
+    fn read(&mut self, ty: Type) -> Result<Option<f64>, String> {
+        match self {
+            Body::Ascii(iter) => match iter.next() {
+                Some(token) => token.parse::<f64>().map(Some).map_err(|_| token.to_string()),
+                None => Ok(None),
+            },
+            Body::Binary { bytes, big_endian } => {
+                if bytes.len() < ty.size() {
+                    return Ok(None);
+                }
+                let (value, rest) = bytes.split_at(ty.size());
+                *bytes = rest;
+                Ok(Some(ty.decode(value, *big_endian)))
+            }
+        }
+    }
+}
+
+/// Parse an ASCII or binary (little or big endian) PLY file. Vertex positions, normals and colors are read from the `vertex` element,
+/// and polygons from the `vertex_indices` list of the `face` element. Polygons are triangulated as a fan. Other elements are skipped
+pub fn parse_file(filename: String) -> Result<Mesh, PlyError> {
+    parse(&fs::read(filename)?)
+}
+
+fn parse(bytes: &[u8]) -> Result<Mesh, PlyError> {
+    // Split off the header, which is always text
+    let end = bytes.windows(10).position(|w| w == b"end_header").ok_or(PlyError::Header { line: 1, text: "missing end_header".to_string() })?;
+    let header = String::from_utf8_lossy(&bytes[..end]);
+    let mut body_start = end + 10;
+    if bytes[body_start..].starts_with(b"\r") {
+        body_start += 1;
+    }
+    if bytes[body_start..].starts_with(b"\n") {
+        body_start += 1;
+    }
+
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    for (i, line) in header.lines().enumerate() {
+        let n = i + 1;
+        let invalid = || PlyError::Header { line: n, text: line.to_string() };
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["ply"] if n == 1 => (),
+            _ if n == 1 => return Err(invalid()),
+            ["format", "ascii", _] => format = Some(None),
+            ["format", "binary_little_endian", _] => format = Some(Some(false)),
+            ["format", "binary_big_endian", _] => format = Some(Some(true)),
+            ["comment", ..] | ["obj_info", ..] | [] => (),
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count.parse().map_err(|_| invalid())?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => {
+                let property = Property::List(name.to_string(), Type::parse(count).ok_or_else(invalid)?, Type::parse(item).ok_or_else(invalid)?);
+                elements.last_mut().ok_or_else(invalid)?.properties.push(property);
+            }
+            ["property", ty, name] => {
+                let property = Property::Scalar(name.to_string(), Type::parse(ty).ok_or_else(invalid)?);
+                elements.last_mut().ok_or_else(invalid)?.properties.push(property);
+            }
+            _ => return Err(invalid()),
+        }
+    }
+
+    let mut body = match format {
+        Some(None) => Body::Ascii(std::str::from_utf8(&bytes[body_start..]).unwrap_or("").split_ascii_whitespace()),
+        Some(Some(big_endian)) => Body::Binary { bytes: &bytes[body_start..], big_endian },
+        None => return Err(PlyError::Header { line: 1, text: "missing format".to_string() }),
+    };
+
+    let mut mesh = Mesh::new();
+    let mut polygons: Vec<Vec<i64>> = Vec::new();
+    for element in &elements {
+        for index in 0..element.count {
+            let mut read = |ty: Type| match body.read(ty) {
+                Ok(Some(value)) => Ok(value),
+                Ok(None) => Err(PlyError::Truncated { element: element.name.clone(), index }),
+                Err(text) => Err(PlyError::MalformedNumber { element: element.name.clone(), index, text }),
+            };
+
+            let mut position = [0.0; 3];
+            let mut normal: [Option<f32>; 3] = [None; 3];
+            let mut color: [Option<f64>; 3] = [None; 3];
+            let mut polygon = Vec::new();
+            for property in &element.properties {
+                match property {
+                    Property::Scalar(name, ty) => {
+                        let value = read(*ty)?;
+                        // Integer colors are 0 to 255, floating point colors 0 to 1
+                        let channel = if ty.is_float() { value * 255.0 } else { value };
+                        match name.as_str() {
+                            "x" => position[0] = value as f32,
+                            "y" => position[1] = value as f32,
+                            "z" => position[2] = value as f32,
+                            "nx" => normal[0] = Some(value as f32),
+                            "ny" => normal[1] = Some(value as f32),
+                            "nz" => normal[2] = Some(value as f32),
+                            "red" | "diffuse_red" => color[0] = Some(channel),
+                            "green" | "diffuse_green" => color[1] = Some(channel),
+                            "blue" | "diffuse_blue" => color[2] = Some(channel),
+                            _ => (),
+                        }
+                    }
+                    Property::List(name, count, item) => {
+                        let count = read(*count)? as usize;
+                        let values = (0..count).map(|_| read(*item)).collect::<Result<Vec<f64>, PlyError>>()?;
+                        if name == "vertex_indices" || name == "vertex_index" {
+                            polygon = values.into_iter().map(|v| v as i64).collect();
+                        }
+                    }
+                }
+            }
+
+            match element.name.as_str() {
+                "vertex" => {
+                    let i = mesh.add_vertex_force(&Vec3d::new(position[0], position[1], position[2]));
+                    if let [Some(r), Some(g), Some(b)] = color {
+                        mesh.set_vertex_color(i, Some(Rgb::new(r, g, b, None)));
+                    }
+                    if let [Some(x), Some(y), Some(z)] = normal {
+                        mesh.set_vertex_normal(i, Some(Vec3d::new(x, y, z)));
+                    }
+                }
+                "face" => polygons.push(polygon),
+                _ => (),
+            }
+        }
+    }
+
+    // Faces are added once every vertex is known, as elements may come in any order
+    for (face, polygon) in polygons.iter().enumerate() {
+        if let Some(&index) = polygon.iter().find(|&&i| i < 0 || i as usize >= mesh.vertex_count()) {
+            return Err(PlyError::IndexOutOfRange { face, index });
+        }
+        for i in 1..polygon.len().saturating_sub(1) {
+            mesh.add_tri(polygon[0] as usize, polygon[i] as usize, polygon[i + 1] as usize, &None);
+        }
+    }
+
+    Ok(mesh)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "ply\nformat {} 1.0\ncomment test\nelement vertex 4\nproperty float x\nproperty float y\nproperty float z\n\
+        property uchar red\nproperty uchar green\nproperty uchar blue\nelement face 1\nproperty list uchar int vertex_indices\nend_header\n";
+    const VERTICES: [([f32; 3], [u8; 3]); 4] = [
+        ([0.0, 0.0, 0.0], [255, 0, 0]),
+        ([1.0, 0.0, 0.0], [0, 255, 0]),
+        ([1.0, 1.0, 0.0], [0, 0, 255]),
+        ([0.0, 1.0, 0.5], [10, 20, 30]),
+    ];
+
+    fn header(format: &str) -> Vec<u8> {
+        HEADER.replace("{}", format).into_bytes()
+    }
+
+    fn binary(big_endian: bool) -> Vec<u8> {
+        let mut bytes = header(if big_endian { "binary_big_endian" } else { "binary_little_endian" });
+        for (position, color) in VERTICES {
+            for c in position {
+                bytes.extend(if big_endian { c.to_be_bytes() } else { c.to_le_bytes() });
+            }
+            bytes.extend(color);
+        }
+        bytes.push(4);
+        for i in 0..4i32 {
+            bytes.extend(if big_endian { i.to_be_bytes() } else { i.to_le_bytes() });
+        }
+        bytes
+    }
+
+    /// Check the quad was read as two triangles, with vertex colors
+    fn check(mut mesh: Mesh) {
+        assert_eq!(mesh.vertex_count(), 4);
+        assert_eq!(mesh.face_count(), 2);
+        let tri = mesh.compile_face(1).unwrap();
+        assert!(tri.vertices.0.equals(&Vec3d::new(0.0, 0.0, 0.0)));
+        assert!(tri.vertices.1.equals(&Vec3d::new(1.0, 1.0, 0.0)));
+        assert!(tri.vertices.2.equals(&Vec3d::new(0.0, 1.0, 0.5)));
+        let (a, _, c) = tri.colors.unwrap();
+        assert_eq!((a.red(), a.green(), a.blue()), (255.0, 0.0, 0.0));
+        assert_eq!((c.red(), c.green(), c.blue()), (10.0, 20.0, 30.0));
+    }
+
+    #[test]
+    fn ascii() {
+        let mut bytes = header("ascii");
+        for (p, c) in VERTICES {
+            bytes.extend(format!("{} {} {} {} {} {}\n", p[0], p[1], p[2], c[0], c[1], c[2]).into_bytes());
+        }
+        bytes.extend(b"4 0 1 2 3\n");
+        check(parse(&bytes).unwrap());
+    }
+
+    #[test]
+    fn binary_little_endian() {
+        check(parse(&binary(false)).unwrap());
+    }
+
+    #[test]
+    fn binary_big_endian() {
+        check(parse(&binary(true)).unwrap());
+    }
+
+    #[test]
+    fn truncated() {
+        let mut bytes = binary(false);
+        bytes.truncate(bytes.len() - 2);
+        assert!(matches!(parse(&bytes), Err(PlyError::Truncated { index: 0, .. })));
+    }
+
+    #[test]
+    fn index_out_of_range() {
+        let mut bytes = binary(false);
+        let len = bytes.len();
+        bytes[len - 4] = 9;
+        assert!(matches!(parse(&bytes), Err(PlyError::IndexOutOfRange { face: 0, index: 9 })));
+    }
+}
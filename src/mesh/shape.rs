@@ -1,5 +1,5 @@
-use crate::util::maths::vec::Vec3d;
-use crate::util::mesh::Mesh;
+use crate::maths::vec::Vec3d;
+use crate::mesh::Mesh;
 
 /// Create a square with sides of length `l`
 pub fn square(l: f32) -> Mesh {
@@ -61,7 +61,7 @@ pub fn circle(r: f32, dtheta: f32) -> Mesh {
 /// Create a tetrahedron with dimensions `dim`
 pub fn tetrahedron(dim: &Vec3d) -> Mesh {
     let mut mesh = Mesh::new();
-    let d = dim.mulk(0.5);
+    let d = *dim * 0.5;
     let v = (
         mesh.add_vertex_force(&Vec3d::new(-d.x, -d.y, -d.z)),
         mesh.add_vertex_force(&Vec3d::new(d.x, -d.y, d.z)),
@@ -85,7 +85,7 @@ pub fn cube(l: f32) -> Mesh {
 /// Create a cuboid with dimensions `dim`
 pub fn cuboid(dim: &Vec3d) -> Mesh {
     let mut mesh = Mesh::new();
-    let hl = dim.mulk(0.5);
+    let hl = *dim * 0.5;
     let v = (
         mesh.add_vertex(&Vec3d::new(-hl.x, -hl.y, -hl.z)),
         mesh.add_vertex(&Vec3d::new(-hl.x, hl.y, -hl.z)),
@@ -110,7 +110,7 @@ pub fn cuboid(dim: &Vec3d) -> Mesh {
 /// Create a triangular prism dimensions `dim`
 pub fn prism(dim: &Vec3d) -> Mesh {
     let mut mesh = Mesh::new();
-    let d = dim.mulk(0.5);
+    let d = *dim * 0.5;
     let v = (
         mesh.add_vertex_force(&Vec3d::new(-d.x, -d.y, -d.z)),
         mesh.add_vertex_force(&Vec3d::new(0.0, d.y, -d.z)),
@@ -173,6 +173,7 @@ pub fn sphere(radius: f32, n_slices: usize, n_stacks: usize) -> Mesh {
 
     // Top
     let vtop = mesh.add_vertex_force(&Vec3d::new(0.0, radius, 0.0));
+    mesh.set_vertex_normal(vtop, Some(Vec3d::unit_y()));
 
     for i in 0..(n_stacks - 1) {
         let phi: f32 = std::f32::consts::PI * (i as f32 + 1.0) / n_stacks as f32;
@@ -181,12 +182,14 @@ pub fn sphere(radius: f32, n_slices: usize, n_stacks: usize) -> Mesh {
             let x = phi.sin() * theta.cos();
             let y = phi.cos();
             let z = phi.sin() * theta.sin();
-            mesh.add_vertex_force(&Vec3d::new(x, y, z));
+            let v = mesh.add_vertex_force(&Vec3d::new(x, y, z));
+            mesh.set_vertex_normal(v, Some(Vec3d::new(x, y, z)));
         }
     }
 
     // Bottom
     let vbot = mesh.add_vertex_force(&Vec3d::new(0.0, -radius, 0.0));
+    mesh.set_vertex_normal(vbot, Some(Vec3d::new(0.0, -1.0, 0.0)));
 
     for i in 0..n_slices {
         let i0 = i + 1;
@@ -0,0 +1,327 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+
+use super::Mesh;
+use super::super::maths::vec::Vec3d;
+
+/// Size of the binary header, including the facet count
+const HEADER_SIZE: usize = 84;
+/// Size of each facet in a binary file: normal, three vertices and an attribute count
+const FACET_SIZE: usize = 50;
+
+/// Error encountered while parsing an STL file. Line numbers start at 1
+#[derive(Debug)]
+pub enum StlError {
+    /// The file could not be opened or read
+    Io(io::Error),
+    /// A number could not be parsed (ASCII only)
+    MalformedNumber { line: usize, text: String },
+    /// A facet does not have at least three vertices (ASCII only)
+    MalformedFacet { line: usize },
+    /// The file is too short to hold the header and facet count (binary only)
+    HeaderTooShort { found: usize },
+    /// The file ended before all facets were read (binary only)
+    Truncated { expected: usize, found: usize },
+}
+
+impl fmt::Display for StlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StlError::Io(e) => write!(f, "Error: {}", e),
+            StlError::MalformedNumber { line, text } => write!(f, "Error (line {}): malformed number '{}'", line, text),
+            StlError::MalformedFacet { line } => write!(f, "Error (line {}): facet has fewer than 3 vertices", line),
+            StlError::HeaderTooShort { found } => write!(f, "Error: file is {} bytes, but the header is {} bytes", found, HEADER_SIZE),
+            StlError::Truncated { expected, found } => write!(f, "Error: expected {} facets, but file only contains {}", expected, found),
+        }
+    }
+}
+
+impl std::error::Error for StlError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StlError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for StlError {
+    fn from(e: io::Error) -> Self {
+        StlError::Io(e)
+    }
+}
+
+/// Builds a mesh from separate triangles, welding identical vertices together
+struct Welder {
+    mesh: Mesh,
+    /// Index of each distinct vertex, keyed by its bits
+    indices: HashMap<(u32, u32, u32), usize>,
+}
+
+impl Welder {
+    fn new() -> Welder {
+        Welder { mesh: Mesh::new(), indices: HashMap::new() }
+    }
+
+    fn vertex(&mut self, v: &Vec3d) -> usize {
+        // Treat -0.0 and 0.0 as the same vertex
+        let key = ((v.x + 0.0).to_bits(), (v.y + 0.0).to_bits(), (v.z + 0.0).to_bits());
+        let mesh = &mut self.mesh;
+        *self.indices.entry(key).or_insert_with(|| mesh.add_vertex_force(v))
+    }
+
+    /// Add a facet. If the stored normal points against the one implied by the vertex order, the winding is reversed
+    fn facet(&mut self, normal: &Vec3d, a: &Vec3d, b: &Vec3d, c: &Vec3d) {
+        let implied = Vec3d::normal(&(*b - *a), &(*c - *a));
+        let (b, c) = if Vec3d::dot_product(normal, &implied) < 0.0 { (c, b) } else { (b, c) };
+        let (a, b, c) = (self.vertex(a), self.vertex(b), self.vertex(c));
+        self.mesh.add_tri(a, b, c, &None);
+    }
+}
+
+/// Is this the contents of a binary STL file? Binary headers may start with "solid" like ASCII files, so an exact size
+/// match, binary bytes or missing keywords also mark a file as binary
+fn is_binary(bytes: &[u8]) -> bool {
+    if bytes.len() >= HEADER_SIZE {
+        let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+        if bytes.len() == HEADER_SIZE + count * FACET_SIZE {
+            return true;
+        }
+    }
+    let contains = |word: &[u8]| bytes.windows(word.len()).any(|w| w == word);
+    !bytes.trim_ascii_start().starts_with(b"solid")
+        || bytes.iter().any(|&b| b == 0 || !b.is_ascii())
+        || !contains(b"facet")
+        || !contains(b"endsolid")
+}
+
+/// Parse an ASCII or binary STL file
+pub fn parse_file(filename: String) -> Result<Mesh, StlError> {
+    parse(&fs::read(filename)?)
+}
+
+fn parse(bytes: &[u8]) -> Result<Mesh, StlError> {
+    if is_binary(bytes) {
+        parse_binary(bytes)
+    } else {
+        parse_ascii(&String::from_utf8_lossy(bytes))
+    }
+}
+
+fn parse_binary(bytes: &[u8]) -> Result<Mesh, StlError> {
+    if bytes.len() < HEADER_SIZE {
+        return Err(StlError::HeaderTooShort { found: bytes.len() });
+    }
+    let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+    let found = (bytes.len() - HEADER_SIZE) / FACET_SIZE;
+    if found < count {
+        return Err(StlError::Truncated { expected: count, found });
+    }
+
+    let mut welder = Welder::new();
+    for facet in bytes[HEADER_SIZE..].chunks_exact(FACET_SIZE).take(count) {
+        let f = |i: usize| f32::from_le_bytes([facet[i * 4], facet[i * 4 + 1], facet[i * 4 + 2], facet[i * 4 + 3]]);
+        let v = |i: usize| Vec3d::new(f(i * 3), f(i * 3 + 1), f(i * 3 + 2));
+        welder.facet(&v(0), &v(1), &v(2), &v(3));
+    }
+    Ok(welder.mesh)
+}
+
+fn parse_ascii(text: &str) -> Result<Mesh, StlError> {
+    let mut welder = Welder::new();
+    let mut normal = Vec3d::origin();
+    let mut vertices: Vec<Vec3d> = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let n = i + 1;
+        let mut iter = line.split_whitespace();
+        let parse_vec = |iter: &mut dyn Iterator<Item = &str>| -> Result<Vec3d, StlError> {
+            let mut c = [0.0; 3];
+            for c in c.iter_mut() {
+                let token = iter.next().unwrap_or("");
+                *c = token.parse::<f32>().map_err(|_| StlError::MalformedNumber { line: n, text: token.to_string() })?;
+            }
+            Ok(Vec3d::new(c[0], c[1], c[2]))
+        };
+
+        match iter.next() {
+            Some("facet") => {
+                iter.next(); // Skip "normal"
+                normal = parse_vec(&mut iter)?;
+                vertices.clear();
+            }
+            Some("vertex") => vertices.push(parse_vec(&mut iter)?),
+            Some("endfacet") => {
+                if vertices.len() < 3 {
+                    return Err(StlError::MalformedFacet { line: n });
+                }
+                // Facets should be triangles, but triangulate any polygon as a fan
+                for i in 1..vertices.len() - 1 {
+                    welder.facet(&normal, &vertices[0], &vertices[i], &vertices[i + 1]);
+                }
+            }
+            _ => (), // solid, outer loop, endloop, endsolid
+        }
+    }
+
+    Ok(welder.mesh)
+}
+
+/// Get the unit normal of each face, or zero for degenerate faces
+fn face_normals(mesh: &Mesh) -> impl Iterator<Item = (Vec3d, [&Vec3d; 3])> {
+    mesh.faces.iter().map(|face| {
+        let (a, b, c) = (&mesh.vertices[face.0], &mesh.vertices[face.1], &mesh.vertices[face.2]);
+        let normal = Vec3d::normal(&(*b - *a), &(*c - *a));
+        let length = normal.length();
+        let normal = if length > 0.0 { normal / length } else { Vec3d::origin() };
+        (normal, [a, b, c])
+    })
+}
+
+/// Write a mesh as an ASCII STL file. Styles are not written
+pub fn write_ascii(mesh: &Mesh, filename: String) -> io::Result<()> {
+    let mut w = BufWriter::new(File::create(filename)?);
+    writeln!(w, "solid mesh")?;
+    for (normal, vertices) in face_normals(mesh) {
+        writeln!(w, "  facet normal {} {} {}", normal.x, normal.y, normal.z)?;
+        writeln!(w, "    outer loop")?;
+        for v in vertices {
+            writeln!(w, "      vertex {} {} {}", v.x, v.y, v.z)?;
+        }
+        writeln!(w, "    endloop")?;
+        writeln!(w, "  endfacet")?;
+    }
+    writeln!(w, "endsolid mesh")?;
+    w.flush()
+}
+
+/// Write a mesh as a binary STL file. Styles are not written
+pub fn write_binary(mesh: &Mesh, filename: String) -> io::Result<()> {
+    let mut w = BufWriter::new(File::create(filename)?);
+    let mut header = [0u8; 80];
+    header[..11].copy_from_slice(b"binary mesh");
+    w.write_all(&header)?;
+    w.write_all(&(mesh.faces.len() as u32).to_le_bytes())?;
+    for (normal, vertices) in face_normals(mesh) {
+        for v in [&normal, vertices[0], vertices[1], vertices[2]] {
+            w.write_all(&v.x.to_le_bytes())?;
+            w.write_all(&v.y.to_le_bytes())?;
+            w.write_all(&v.z.to_le_bytes())?;
+        }
+        w.write_all(&0u16.to_le_bytes())?;
+    }
+    w.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::shape;
+    use crate::test_util::TempPath;
+
+    /// Check that `read` has the same faces as `mesh`, each starting from the same corner
+    fn assert_same_faces(mesh: &Mesh, read: &Mesh) {
+        assert_eq!(read.faces.len(), mesh.faces.len());
+        for (a, b) in mesh.faces.iter().zip(&read.faces) {
+            assert_eq!(mesh.vertices[a.0], read.vertices[b.0]);
+            assert_eq!(mesh.vertices[a.1], read.vertices[b.1]);
+            assert_eq!(mesh.vertices[a.2], read.vertices[b.2]);
+        }
+    }
+
+    #[test]
+    fn ascii_round_trip() {
+        let mesh = shape::cube(2.0);
+        let path = TempPath::new("stl_ascii.stl");
+        write_ascii(&mesh, path.name()).unwrap();
+        let read = parse_file(path.name()).unwrap();
+
+        assert_eq!(read.vertices.len(), 8);
+        assert_same_faces(&mesh, &read);
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let mesh = shape::cube(2.0);
+        let path = TempPath::new("stl_binary.stl");
+        write_binary(&mesh, path.name()).unwrap();
+        let bytes = std::fs::read(path.path()).unwrap();
+        let read = parse_file(path.name()).unwrap();
+
+        assert_eq!(bytes.len(), HEADER_SIZE + 12 * FACET_SIZE);
+        assert_eq!(read.vertices.len(), 8);
+        assert_same_faces(&mesh, &read);
+    }
+
+    #[test]
+    fn shared_corners_weld() {
+        let text = "solid quad
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 1 1 0
+    endloop
+  endfacet
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 1 0
+      vertex -0 1 0
+    endloop
+  endfacet
+endsolid quad
+";
+        let mesh = parse_ascii(text).unwrap();
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.faces.iter().map(|f| (f.0, f.1, f.2)).collect::<Vec<_>>(), vec![(0, 1, 2), (0, 2, 3)]);
+    }
+
+    #[test]
+    fn winding_follows_normal() {
+        // Counter-clockwise seen from +z, but the stored normal points along -z
+        let text = "solid flipped
+  facet normal 0 0 -1
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 0 1 0
+    endloop
+  endfacet
+endsolid flipped
+";
+        let mesh = parse_ascii(text).unwrap();
+        assert_eq!(mesh.faces[0].0, 0);
+        assert_eq!(mesh.vertices[mesh.faces[0].1], Vec3d::new(0.0, 1.0, 0.0));
+        assert_eq!(mesh.vertices[mesh.faces[0].2], Vec3d::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn truncated_binary() {
+        let mut bytes = vec![0u8; HEADER_SIZE + FACET_SIZE];
+        bytes[80..84].copy_from_slice(&2u32.to_le_bytes());
+        assert!(matches!(parse_binary(&bytes), Err(StlError::Truncated { expected: 2, found: 1 })));
+        assert!(matches!(parse_binary(&bytes[..40]), Err(StlError::HeaderTooShort { found: 40 })));
+    }
+
+    #[test]
+    fn binary_with_solid_header() {
+        let mesh = shape::cube(2.0);
+        let path = TempPath::new("stl_solid_header.stl");
+        write_binary(&mesh, path.name()).unwrap();
+        let mut bytes = std::fs::read(path.path()).unwrap();
+        bytes[..11].copy_from_slice(b"solid mesh ");
+
+        assert_same_faces(&mesh, &parse(&bytes).unwrap());
+        // Padding after the last facet
+        let mut padded = bytes.clone();
+        padded.extend_from_slice(b"endsolid\n");
+        assert_same_faces(&mesh, &parse(&padded).unwrap());
+        // Truncation is reported rather than parsed as an empty ASCII solid
+        let end = bytes.len() - FACET_SIZE / 2;
+        assert!(matches!(parse(&bytes[..end]), Err(StlError::Truncated { expected: 12, found: 11 })));
+        assert!(matches!(parse(&bytes[..40]), Err(StlError::HeaderTooShort { found: 40 })));
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::VecDeque;
+
+use crate::camera::Viewpoint;
+use crate::lighting::{Light, Lighting};
+use crate::maths::mat::Mat4x4;
+use crate::maths::vec::{Vec3d, Vec4d};
+use crate::mesh::{Mesh, Shading};
+use crate::raster::Framebuffer;
+use crate::scene::Scene;
+use crate::texture::Filter;
+use crate::triangle::Triangle;
+
+/// Vertical field of view, in degrees
+pub const FOV: f32 = 90.0;
+/// Distance to the near clipping plane
+pub const NEAR: f32 = 0.1;
+/// Distance to the far clipping plane
+pub const FAR: f32 = 1000.0;
+
+/// Construct the default perspective projection for a screen of the given size
+pub fn perspective(width: f32, height: f32) -> Mat4x4 {
+    Mat4x4::perspective(FOV.to_radians(), width / height, NEAR, FAR)
+}
+
+/// Construct an orthographic projection for a screen of the given size, showing as much at `distance` from the camera as `perspective` does
+pub fn orthographic(distance: f32, width: f32, height: f32) -> Mat4x4 {
+    let top = distance * (FOV.to_radians() * 0.5).tan();
+    let right = top * width / height;
+    Mat4x4::orthographic(-right, right, -top, top, NEAR, FAR)
+}
+
+/// Model to world space: place a triangle in the world
+pub fn to_world(tri: &mut Triangle, world: &Mat4x4) {
+    let (a, b, c) = tri.vertices;
+    tri.vertices = (a * *world, b * *world, c * *world);
+}
+
+/// World to view space: move a triangle so the camera is at the origin, looking along +z
+pub fn to_view(tri: &mut Triangle, view: &Mat4x4) {
+    let (a, b, c) = tri.vertices;
+    tri.vertices = (a * *view, b * *view, c * *view);
+}
+
+/// View to clip space: project a triangle, setting its `clip` positions. Nothing is divided by `w` yet
+pub fn to_clip(tri: &mut Triangle, proj: &Mat4x4) {
+    let (a, b, c) = tri.vertices;
+    let project = |v: Vec3d| Vec4d::from_point(&v) * *proj;
+    tri.clip = Some((project(a), project(b), project(c)));
+}
+
+/// Clip a triangle in clip space to the view volume, where `x` and `y` lie between `-w` and `w`, and `z` between 0 and `w`.
+/// Clipping at the near plane first ensures every remaining point is in front of the camera, so `w` is positive
+pub fn clip(tri: &Triangle) -> Vec<Triangle> {
+    let planes = [
+        Vec4d::new(0.0, 0.0, 1.0, 0.0), // Near
+        Vec4d::new(0.0, 0.0, -1.0, 1.0), // Far
+        Vec4d::new(0.0, -1.0, 0.0, 1.0), // Top
+        Vec4d::new(0.0, 1.0, 0.0, 1.0), // Bottom
+        Vec4d::new(1.0, 0.0, 0.0, 1.0), // Left
+        Vec4d::new(-1.0, 0.0, 0.0, 1.0), // Right
+    ];
+
+    // Triangles produced by one plane are queued to be tested against the rest
+    let mut queue: VecDeque<Triangle> = VecDeque::new();
+    queue.push_back(tri.clone());
+    for plane in &planes {
+        for _ in 0..queue.len() {
+            let tri = queue.pop_front().unwrap();
+            queue.extend(tri.clip_against_plane(plane));
+        }
+    }
+    queue.into()
+}
+
+/// Perspective divide: take a triangle from clip space to normalised device coordinates, where the view volume runs from -1 to 1
+/// in `x` and `y`, and 0 to 1 in `z`. The reciprocal of each `w` is kept, to interpolate vertex data with perspective
+pub fn divide(tri: &mut Triangle) {
+    if let Some((a, b, c)) = tri.clip.take() {
+        tri.vertices = (a.divide(), b.divide(), c.divide());
+        tri.inv_w = Some((1.0 / a.w, 1.0 / b.w, 1.0 / c.w));
+    }
+}
+
+/// Viewport transform: take a triangle from normalised device coordinates to screen space, in pixels with `y` pointing down.
+/// `z` is offset by one, but still orders points by depth
+pub fn viewport(tri: &mut Triangle, width: f32, height: f32) {
+    let flip = Vec3d::new(1.0, -1.0, 1.0);
+    let scale = Vec3d::new(0.5 * width, 0.5 * height, 1.0);
+    let screen = |v: &Vec3d| v.mul(&flip).addk(1.0).mul(&scale);
+    tri.vertices = (screen(&tri.vertices.0), screen(&tri.vertices.1), screen(&tri.vertices.2));
+}
+
+/// Everything needed to take a mesh from model space to screen space
+pub struct Pipeline {
+    /// Transforms model space into world space
+    pub world: Mat4x4,
+    /// Transforms world space into view space
+    pub view: Mat4x4,
+    /// Position of the camera in world space
+    pub camera: Vec3d,
+    /// Transforms view space into clip space. May be a perspective or orthographic projection
+    pub proj: Mat4x4,
+    /// Lights, and the viewer's position for specular highlights
+    pub lighting: Lighting,
+    /// How to shade faces whose style does not say
+    pub shading: Shading,
+    /// How textures are sampled
+    pub filter: Filter,
+    /// Screen width in pixels
+    pub width: f32,
+    /// Screen height in pixels
+    pub height: f32,
+}
+
+impl Pipeline {
+    /// Construct a pipeline looking through `camera` onto a screen of the given size, using the default projection
+    pub fn new(world: Mat4x4, camera: &dyn Viewpoint, lights: Vec<Light>, width: f32, height: f32) -> Pipeline {
+        Pipeline {
+            world,
+            view: camera.view_matrix(),
+            camera: camera.position(),
+            proj: perspective(width, height),
+            lighting: Lighting::new(lights, &camera.position()),
+            shading: Shading::Flat,
+            filter: Filter::Bilinear,
+            width,
+            height,
+        }
+    }
+
+    /// Get the direction in which the camera looks at `point`, in world space
+    fn view_ray(&self, point: &Vec3d) -> Vec3d {
+        if self.proj.is_affine() {
+            // Orthographic cameras look along their forward direction everywhere
+            Vec3d::new(self.view.0.2, self.view.1.2, self.view.2.2)
+        } else {
+            *point - self.camera
+        }
+    }
+
+    /// Transform, cull, light, project and clip every face of `mesh`. Return the visible triangles in screen space, where `z` is depth
+    pub fn process(&self, mesh: &mut Mesh) -> Vec<Triangle> {
+        self.process_with(mesh, &self.world)
+    }
+
+    /// Process every mesh drawn by `scene`, placed by its node and then by `world`
+    pub fn process_scene(&self, scene: &mut Scene) -> Vec<Triangle> {
+        let mut to_draw = Vec::new();
+        for (mesh, matrix) in scene.instances() {
+            to_draw.extend(self.process_with(&mut scene.meshes[mesh], &(matrix * self.world)));
+        }
+        to_draw
+    }
+
+    /// Process `mesh` as `process`, with `world` transforming it into world space
+    fn process_with(&self, mesh: &mut Mesh, world: &Mat4x4) -> Vec<Triangle> {
+        // Normals are carried by the inverse transpose, which keeps them perpendicular to their faces under non-uniform scaling.
+        // A mirroring transform turns faces inside out, so their normals must be flipped
+        let normal_matrix = world.normal_matrix();
+        let mirrored = world.determinant() < 0.0;
+        let mut to_draw: Vec<Triangle> = Vec::new();
+
+        for i in 0..mesh.face_count() {
+            let mut tri = mesh.compile_face(i).unwrap();
+            tri.filter = self.filter;
+            to_world(&mut tri, world);
+
+            // Calculate the normal
+            let mut normal = tri.normal().normalise();
+            if mirrored {
+                normal = -normal;
+            }
+
+            // Check if triangle is visible - less than 90deg to the camera
+            let cam_ray = self.view_ray(&tri.vertices.0);
+            if Vec3d::dot_product(&normal, &cam_ray) < 0.0 {
+                self.light(&mut tri, &normal, &normal_matrix);
+                to_view(&mut tri, &self.view);
+                to_clip(&mut tri, &self.proj);
+                for mut tri in clip(&tri) {
+                    divide(&mut tri);
+                    viewport(&mut tri, self.width, self.height);
+                    to_draw.push(tri);
+                }
+            }
+        }
+
+        to_draw
+    }
+
+    /// Light a triangle in world space with face normal `normal`. Vertex normals are carried into world space by `normal_matrix`
+    fn light(&self, tri: &mut Triangle, normal: &Vec3d, normal_matrix: &Mat4x4) {
+        // Illumination of the whole face, at its centre
+        let (a, b, c) = &tri.vertices;
+        let centre = (*a + *b + *c) * (1.0 / 3.0);
+        tri.light = self.lighting.light(&tri.material, normal, &centre);
+
+        // Illumination of each vertex, and of each pixel
+        let shading = tri.shading.unwrap_or(self.shading);
+        if shading != Shading::Flat {
+            if let Some((na, nb, nc)) = &tri.normals {
+                let world = |n: &Vec3d| Vec3d::mult_mat_dir(n, normal_matrix).normalise();
+                let normals = (world(na), world(nb), world(nc));
+                let light = |n: &Vec3d, p: &Vec3d| self.lighting.light(&tri.material, n, p);
+                tri.lights = Some((light(&normals.0, a), light(&normals.1, b), light(&normals.2, c)));
+                if shading == Shading::PerPixel {
+                    tri.world = Some((*a, *b, *c));
+                }
+                tri.normals = Some(normals);
+            }
+        }
+    }
+
+    /// Render a single frame of `mesh` without a window, using the depth buffer
+    pub fn render(&self, mesh: &mut Mesh) -> Framebuffer {
+        self.draw(&mut self.process(mesh))
+    }
+
+    /// Render a single frame of every mesh drawn by `scene`, as `render`
+    pub fn render_scene(&self, scene: &mut Scene) -> Framebuffer {
+        self.draw(&mut self.process_scene(scene))
+    }
+
+    /// Rasterize triangles in screen space into a new framebuffer
+    fn draw(&self, to_draw: &mut [Triangle]) -> Framebuffer {
+        let mut framebuffer = Framebuffer::new(self.width as usize, self.height as usize);
+        for tri in to_draw {
+            framebuffer.draw_triangle(tri, &self.lighting);
+        }
+        framebuffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::assert_vec;
+
+    /// Depth 0 at 1 and 1 at 11, showing as far to each side as ahead
+    fn proj() -> Mat4x4 {
+        Mat4x4::perspective(90.0f32.to_radians(), 1.0, 1.0, 11.0)
+    }
+
+    #[test]
+    fn stages() {
+        let mut tri = Triangle::new(Vec3d::new(0.0, 0.0, 0.0), Vec3d::new(1.0, 0.0, 0.0), Vec3d::new(0.0, 1.0, 0.0));
+        to_world(&mut tri, &Mat4x4::translation(0.0, 0.0, 1.0));
+        assert_vec(&tri.vertices.1, 1.0, 0.0, 1.0);
+        // The camera sits at z = -1
+        to_view(&mut tri, &Mat4x4::translation(0.0, 0.0, 1.0));
+        assert_vec(&tri.vertices.1, 1.0, 0.0, 2.0);
+
+        to_clip(&mut tri, &proj());
+        let (a, b, _) = tri.clip.unwrap();
+        assert_eq!((a.w, b.w), (2.0, 2.0));
+        assert!((b.x - 1.0).abs() < 1e-5 && (b.z - 1.1).abs() < 1e-5, "{:?}", b);
+
+        let out = clip(&tri);
+        assert_eq!(out.len(), 1);
+        let mut tri = out[0].clone();
+        divide(&mut tri);
+        assert!(tri.clip.is_none());
+        assert_eq!(tri.inv_w, Some((0.5, 0.5, 0.5)));
+        assert_vec(&tri.vertices.1, 0.5, 0.0, 0.55);
+
+        viewport(&mut tri, 200.0, 100.0);
+        assert_vec(&tri.vertices.0, 100.0, 50.0, 1.55);
+        assert_vec(&tri.vertices.1, 150.0, 50.0, 1.55);
+        assert_vec(&tri.vertices.2, 100.0, 25.0, 1.55);
+    }
+
+    #[test]
+    fn clip_frustum() {
+        let clipped = |a: Vec3d, b: Vec3d, c: Vec3d| {
+            let mut tri = Triangle::new(a, b, c);
+            to_clip(&mut tri, &proj());
+            clip(&tri)
+        };
+
+        // Entirely inside
+        assert_eq!(clipped(Vec3d::new(0.0, 0.0, 2.0), Vec3d::new(1.0, 0.0, 2.0), Vec3d::new(0.0, 1.0, 2.0)).len(), 1);
+        // Entirely beyond the right edge, behind the camera, or past the far plane
+        assert!(clipped(Vec3d::new(3.0, 0.0, 2.0), Vec3d::new(4.0, 0.0, 2.0), Vec3d::new(3.0, 1.0, 2.0)).is_empty());
+        assert!(clipped(Vec3d::new(0.0, 0.0, -2.0), Vec3d::new(1.0, 0.0, -2.0), Vec3d::new(0.0, 1.0, -2.0)).is_empty());
+        assert!(clipped(Vec3d::new(0.0, 0.0, 12.0), Vec3d::new(1.0, 0.0, 12.0), Vec3d::new(0.0, 1.0, 12.0)).is_empty());
+
+        // Reaching from in front of the camera to behind it: what remains is in front of the near plane, and inside the view
+        let out = clipped(Vec3d::new(0.0, 0.0, 5.0), Vec3d::new(1.0, 0.0, -5.0), Vec3d::new(0.0, 1.0, -5.0));
+        assert!(!out.is_empty());
+        for mut tri in out {
+            let (a, b, c) = tri.clip.unwrap();
+            for v in [a, b, c] {
+                assert!(v.z >= -1e-5 && v.w > 0.0, "{:?}", v);
+            }
+            divide(&mut tri);
+            for v in [tri.vertices.0, tri.vertices.1, tri.vertices.2] {
+                assert!(v.x.abs() <= 1.0 + 1e-5 && v.y.abs() <= 1.0 + 1e-5 && v.z >= -1e-5, "{:?}", v);
+            }
+        }
+    }
+}
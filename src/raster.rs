@@ -0,0 +1,263 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use colorsys::Rgb;
+
+use crate::lighting::Lighting;
+use crate::maths::vec::Vec3d;
+use crate::triangle::Triangle;
+
+/// Convert a color to RGBA bytes
+fn to_rgba(rgb: &Rgb) -> [u8; 4] {
+    [
+        rgb.red().round().clamp(0.0, 255.0) as u8,
+        rgb.green().round().clamp(0.0, 255.0) as u8,
+        rgb.blue().round().clamp(0.0, 255.0) as u8,
+        255,
+    ]
+}
+
+/// Twice the signed area of the triangle `a`, `b`, `p`. Positive if `p` lies to the right of `a`->`b` (with y pointing down)
+fn edge(a: &Vec3d, b: &Vec3d, px: f32, py: f32) -> f32 {
+    (b.x - a.x) * (py - a.y) - (b.y - a.y) * (px - a.x)
+}
+
+/// A flat colored triangle in screen space, for drawing with a 2D graphics library
+pub struct Polygon {
+    /// Position of each corner in screen space
+    pub points: [(f32, f32); 3],
+    /// Shaded fill color. If nothing, no fill
+    pub fill: Option<Rgb>,
+    /// Stroke color. If nothing, no stroke
+    pub stroke: Option<Rgb>,
+}
+
+/// Sort triangles in screen space back to front by their average depth, and get each as a flat shaded polygon.
+/// Drawn in order, nearer polygons cover further ones without a depth buffer (the painter's algorithm)
+pub fn painter(mut tris: Vec<Triangle>) -> Vec<Polygon> {
+    tris.sort_by(|a, b| b.mid_z().total_cmp(&a.mid_z()));
+    tris.iter_mut()
+        .map(|tri| {
+            let (a, b, c) = tri.vertices;
+            Polygon { points: [(a.x, a.y), (b.x, b.y), (c.x, c.y)], fill: tri.get_fill(), stroke: tri.get_stroke() }
+        })
+        .collect()
+}
+
+/// RGBA image with a depth value per pixel, which triangles in screen space are drawn into
+pub struct Framebuffer {
+    width: usize,
+    height: usize,
+    /// RGBA bytes, row by row
+    pixels: Vec<u8>,
+    /// Depth of each pixel. Smaller is closer
+    depth: Vec<f32>,
+}
+
+impl Framebuffer {
+    /// Construct a black framebuffer of the given size
+    pub fn new(width: usize, height: usize) -> Framebuffer {
+        Framebuffer {
+            width,
+            height,
+            pixels: [0, 0, 0, 255].repeat(width * height),
+            depth: vec![f32::INFINITY; width * height],
+        }
+    }
+
+    /// Get width in pixels
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Get height in pixels
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Get RGBA bytes, row by row
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Write to an image file. The format is chosen by extension: `.png`, otherwise binary PPM
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let is_png = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+        let file = BufWriter::new(File::create(path)?);
+        if is_png {
+            self.write_png(file)
+        } else {
+            self.write_ppm(file)
+        }
+    }
+
+    /// Write as a binary (P6) PPM image. Alpha is discarded
+    pub fn write_ppm<W: Write>(&self, mut w: W) -> io::Result<()> {
+        write!(w, "P6\n{} {}\n255\n", self.width, self.height)?;
+        for pixel in self.pixels.chunks_exact(4) {
+            w.write_all(&pixel[..3])?;
+        }
+        w.flush()
+    }
+
+    /// Write as an RGBA PNG image
+    pub fn write_png<W: Write>(&self, w: W) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(w, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        Ok(())
+    }
+
+    /// Fill with the given color, and reset every pixel's depth
+    pub fn clear(&mut self, color: [u8; 4]) {
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&color);
+        }
+        self.depth.fill(f32::INFINITY);
+    }
+
+    /// Set pixel to `color` if `z` is closer than what is already there
+    fn plot(&mut self, x: usize, y: usize, z: f32, color: &[u8; 4]) {
+        let i = y * self.width + x;
+        if z <= self.depth[i] {
+            self.depth[i] = z;
+            self.pixels[i * 4..i * 4 + 4].copy_from_slice(color);
+        }
+    }
+
+    /// Draw a triangle whose vertices are in screen space (`z` being depth), with its fill and stroke. `lighting` is used for triangles lit per pixel
+    pub fn draw_triangle(&mut self, tri: &mut Triangle, lighting: &Lighting) {
+        if tri.colors.is_some() || tri.lights.is_some() || (tri.texture.is_some() && tri.uvs.is_some()) {
+            self.fill_triangle(tri, None, lighting);
+        } else if let Some(rgb) = tri.get_fill() {
+            self.fill_triangle(tri, Some(&to_rgba(&rgb)), lighting);
+        }
+        if let Some(rgb) = tri.get_stroke() {
+            let color = to_rgba(&rgb);
+            let (a, b, c) = &tri.vertices;
+            self.draw_line(a, b, &color);
+            self.draw_line(b, c, &color);
+            self.draw_line(c, a, &color);
+        }
+    }
+
+    /// Scan-convert a triangle, testing every pixel centre inside its bounding box. If no `color` is given, it is found at each pixel
+    fn fill_triangle(&mut self, tri: &Triangle, color: Option<&[u8; 4]>, lighting: &Lighting) {
+        let (a, b, c) = &tri.vertices;
+        let area = edge(a, b, c.x, c.y);
+        if area == 0.0 {
+            return;
+        }
+
+        let min_x = a.x.min(b.x).min(c.x).floor().max(0.0) as usize;
+        let min_y = a.y.min(b.y).min(c.y).floor().max(0.0) as usize;
+        let max_x = (a.x.max(b.x).max(c.x).ceil().max(0.0) as usize).min(self.width);
+        let max_y = (a.y.max(b.y).max(c.y).ceil().max(0.0) as usize).min(self.height);
+
+        for y in min_y..max_y {
+            let py = y as f32 + 0.5;
+            for x in min_x..max_x {
+                let px = x as f32 + 0.5;
+
+                // Barycentric weights. Dividing by the area makes them positive inside the triangle, whatever its winding
+                let w0 = edge(b, c, px, py) / area;
+                let w1 = edge(c, a, px, py) / area;
+                let w2 = edge(a, b, px, py) / area;
+                if w0 >= 0.0 && w1 >= 0.0 && w2 >= 0.0 {
+                    // Depth after projection is linear in screen space, so may be interpolated directly
+                    let z = w0 * a.z + w1 * b.z + w2 * c.z;
+                    match color {
+                        Some(color) => self.plot(x, y, z, color),
+                        None => {
+                            let w = tri.perspective([w0, w1, w2]);
+                            if let Some(rgb) = tri.fill_at(w) {
+                                let light = match tri.surface_at(w) {
+                                    Some((position, normal)) => lighting.light(&tri.material, &normal, &position),
+                                    None => tri.light_at(w),
+                                };
+                                self.plot(x, y, z, &to_rgba(&Triangle::shade(&rgb, &light)));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Draw a line between two points in screen space, interpolating depth
+    fn draw_line(&mut self, from: &Vec3d, to: &Vec3d, color: &[u8; 4]) {
+        let d = *to - *from;
+        let steps = d.x.abs().max(d.y.abs()).ceil().max(1.0) as usize;
+        for i in 0..=steps {
+            let p = *from + d * (i as f32 / steps as f32);
+            if p.x >= 0.0 && p.y >= 0.0 && (p.x as usize) < self.width && (p.y as usize) < self.height {
+                self.plot(p.x as usize, p.y as usize, p.z, color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A triangle in screen space at depth `z`, filled with `rgb` under full white light
+    fn flat(z: f32, rgb: Rgb) -> Triangle {
+        let mut tri = Triangle::new(Vec3d::new(1.0, 1.0, z), Vec3d::new(7.0, 1.0, z), Vec3d::new(1.0, 7.0, z));
+        tri.fill = Some(rgb);
+        tri.stroke = None;
+        tri
+    }
+
+    #[test]
+    fn painter_order() {
+        let near = flat(0.2, Rgb::new(255.0, 0.0, 0.0, None));
+        let mut far = flat(0.8, Rgb::new(0.0, 0.0, 255.0, None));
+        far.stroke = Some(Rgb::new(0.0, 255.0, 0.0, None));
+
+        let polygons = painter(vec![near, far]);
+        assert_eq!(polygons.len(), 2);
+        assert_eq!(polygons[0].points, [(1.0, 1.0), (7.0, 1.0), (1.0, 7.0)]);
+        assert_eq!(polygons[0].fill.as_ref().map(|rgb| rgb.blue().round()), Some(255.0));
+        assert_eq!(polygons[0].stroke.as_ref().map(|rgb| rgb.green()), Some(255.0));
+        assert_eq!(polygons[1].fill.as_ref().map(|rgb| rgb.red().round()), Some(255.0));
+        assert!(polygons[1].stroke.is_none());
+    }
+
+    #[test]
+    fn ppm() {
+        let mut frame = Framebuffer::new(2, 1);
+        frame.clear([10, 20, 30, 255]);
+        frame.plot(1, 0, 0.5, &[200, 100, 50, 128]);
+        let mut bytes = Vec::new();
+        frame.write_ppm(&mut bytes).unwrap();
+
+        // Header, then the RGB of each pixel without alpha
+        let mut expected = b"P6\n2 1\n255\n".to_vec();
+        expected.extend([10, 20, 30, 200, 100, 50]);
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn depth_buffer() {
+        let lighting = Lighting::new(Vec::new(), &Vec3d::origin());
+        let near = || flat(0.2, Rgb::new(255.0, 0.0, 0.0, None));
+        let far = || flat(0.8, Rgb::new(0.0, 0.0, 255.0, None));
+
+        // The nearer triangle shows wherever they overlap, whichever is drawn first
+        for order in [[near(), far()], [far(), near()]] {
+            let mut frame = Framebuffer::new(8, 8);
+            for mut tri in order {
+                frame.draw_triangle(&mut tri, &lighting);
+            }
+            let i = (2 * 8 + 2) * 4;
+            assert_eq!(&frame.pixels()[i..i + 4], &[255, 0, 0, 255]);
+            assert_eq!(frame.depth[2 * 8 + 2], 0.2);
+            // Pixels outside both are left black
+            assert_eq!(&frame.pixels()[(7 * 8 + 7) * 4..], &[0, 0, 0, 255]);
+        }
+    }
+}
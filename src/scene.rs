@@ -0,0 +1,215 @@
+use crate::maths::mat::Mat4x4;
+use crate::maths::quat::Quat;
+use crate::maths::vec::Vec3d;
+use crate::mesh::Mesh;
+
+/// Position, orientation and size of a node relative to its parent. Applied as scale, then rotation, then translation
+pub struct Transform {
+    pub translation: Vec3d,
+    /// Unit quaternion
+    pub rotation: Quat,
+    pub scale: Vec3d,
+}
+
+impl Clone for Transform {
+    fn clone(&self) -> Self {
+        Transform {
+            translation: self.translation,
+            rotation: self.rotation,
+            scale: self.scale,
+        }
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform {
+            translation: Vec3d::origin(),
+            rotation: Quat::identity(),
+            scale: Vec3d::diag(1.0),
+        }
+    }
+}
+
+impl Transform {
+    /// Get the matrix transforming the node's space into its parent's space
+    pub fn matrix(&self) -> Mat4x4 {
+        let (s, t) = (&self.scale, &self.translation);
+        Mat4x4::scale(s.x, s.y, s.z) * self.rotation.to_mat4x4() * Mat4x4::translation(t.x, t.y, t.z)
+    }
+}
+
+/// A node of a scene, placed relative to its parent
+pub struct Node {
+    pub transform: Transform,
+    /// Index into `Scene::meshes` of the mesh drawn at this node, if any
+    pub mesh: Option<usize>,
+    /// Indices into `Scene::nodes` of this node's children
+    pub children: Vec<usize>,
+}
+
+impl Node {
+    /// Construct a node drawing `mesh` (if any) with the given transform and no children
+    pub fn new(transform: Transform, mesh: Option<usize>) -> Node {
+        Node { transform, mesh, children: Vec::new() }
+    }
+}
+
+/// Meshes, and a hierarchy of nodes placing them in the world. A mesh may be drawn by any number of nodes without being copied.
+/// Nodes must form a tree: no node may be the child of more than one node, or of itself
+pub struct Scene {
+    pub meshes: Vec<Mesh>,
+    pub nodes: Vec<Node>,
+    /// Indices into `nodes` of the nodes with no parent
+    pub roots: Vec<usize>,
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Scene::new()
+    }
+}
+
+impl Scene {
+    /// Construct an empty scene
+    pub fn new() -> Scene {
+        Scene { meshes: Vec::new(), nodes: Vec::new(), roots: Vec::new() }
+    }
+
+    /// Construct a scene drawing `mesh` once, where it is
+    pub fn with_mesh(mesh: Mesh) -> Scene {
+        let mut scene = Scene::new();
+        let mesh = scene.add_mesh(mesh);
+        scene.add_node(Node::new(Transform::default(), Some(mesh)), None);
+        scene
+    }
+
+    /// Add a mesh, returning its index for nodes to refer to
+    pub fn add_mesh(&mut self, mesh: Mesh) -> usize {
+        self.meshes.push(mesh);
+        self.meshes.len() - 1
+    }
+
+    /// Add a node as a child of `parent`, or as a root if nothing. Return its index
+    pub fn add_node(&mut self, node: Node, parent: Option<usize>) -> usize {
+        self.nodes.push(node);
+        let i = self.nodes.len() - 1;
+        match parent {
+            Some(parent) => self.nodes[parent].children.push(i),
+            None => self.roots.push(i),
+        }
+        i
+    }
+
+    /// Get each mesh drawn by the scene, as an index into `meshes`, with the matrix transforming it into world space.
+    /// A mesh appears once for every node drawing it
+    pub fn instances(&self) -> Vec<(usize, Mat4x4)> {
+        let mut instances = Vec::new();
+        let mut stack: Vec<(usize, Mat4x4)> = self.roots.iter().map(|&i| (i, Mat4x4::identity())).collect();
+        while let Some((i, parent)) = stack.pop() {
+            let node = &self.nodes[i];
+            let world = node.transform.matrix() * parent;
+            for &child in &node.children {
+                stack.push((child, world));
+            }
+            if let Some(mesh) = node.mesh {
+                instances.push((mesh, world));
+            }
+        }
+        instances
+    }
+
+    /// Combine every instance into a single mesh in world space, copying each mesh once for every node drawing it.
+    /// Only needed to save the scene in formats without a hierarchy; rendering draws instances directly
+    pub fn flatten(&self) -> Mesh {
+        let mut mesh = Mesh::new();
+        for (i, world) in self.instances() {
+            let mut instance = self.meshes[i].clone();
+            instance.transform(&world);
+            mesh.merge(&instance);
+        }
+        mesh
+    }
+
+    /// Get the minimum and maximum corners of a box in world space containing every instance. Return None if nothing is drawn
+    pub fn bounds(&self) -> Option<(Vec3d, Vec3d)> {
+        let mut bounds: Option<(Vec3d, Vec3d)> = None;
+        for (mesh, world) in self.instances() {
+            let Some((min, max)) = self.meshes[mesh].bounds() else { continue };
+            // The transformed box is contained by the box around its transformed corners
+            for i in 0..8 {
+                let corner = Vec3d::new(
+                    if i & 1 == 0 { min.x } else { max.x },
+                    if i & 2 == 0 { min.y } else { max.y },
+                    if i & 4 == 0 { min.z } else { max.z },
+                );
+                let v = corner * world;
+                bounds = Some(match bounds {
+                    Some((min, max)) => (
+                        Vec3d::new(min.x.min(v.x), min.y.min(v.y), min.z.min(v.z)),
+                        Vec3d::new(max.x.max(v.x), max.y.max(v.y), max.z.max(v.z)),
+                    ),
+                    None => (v, v),
+                });
+            }
+        }
+        bounds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::shape;
+    use crate::test_util::assert_vec;
+
+    #[test]
+    fn transform_order() {
+        // Scale, then rotate a quarter turn around Z, then translate
+        let transform = Transform {
+            translation: Vec3d::new(10.0, 0.0, 0.0),
+            rotation: Quat::from_axis_angle(&Vec3d::unit_z(), std::f32::consts::FRAC_PI_2),
+            scale: Vec3d::new(2.0, 1.0, 1.0),
+        };
+        let v = Vec3d::mult_mat(&Vec3d::new(1.0, 0.0, 0.0), &transform.matrix());
+        assert_vec(&v, 10.0, -2.0, 0.0);
+    }
+
+    #[test]
+    fn hierarchy() {
+        let mut scene = Scene::new();
+        let cube = scene.add_mesh(shape::cube(1.0));
+        let parent = scene.add_node(Node::new(Transform { translation: Vec3d::new(0.0, 0.0, 5.0), scale: Vec3d::diag(2.0), ..Default::default() }, None), None);
+        let child = scene.add_node(Node::new(Transform { translation: Vec3d::new(1.0, 0.0, 0.0), ..Default::default() }, Some(cube)), Some(parent));
+        scene.add_node(Node::new(Transform { translation: Vec3d::new(0.0, 1.0, 0.0), ..Default::default() }, Some(cube)), Some(child));
+        scene.add_node(Node::new(Transform::default(), Some(cube)), None);
+
+        // One mesh, drawn three times
+        assert_eq!(scene.meshes.len(), 1);
+        let instances = scene.instances();
+        assert_eq!(instances.len(), 3);
+        let mut origins: Vec<Vec3d> = instances.iter().map(|(mesh, world)| {
+            assert_eq!(*mesh, cube);
+            Vec3d::mult_mat(&Vec3d::origin(), world)
+        }).collect();
+        origins.sort_by(|a, b| a.x.partial_cmp(&b.x).unwrap().then(a.y.partial_cmp(&b.y).unwrap()));
+
+        // The child is moved then scaled by its parent, and the grandchild by both
+        assert_vec(&origins[0], 0.0, 0.0, 0.0);
+        assert_vec(&origins[1], 2.0, 0.0, 5.0);
+        assert_vec(&origins[2], 2.0, 2.0, 5.0);
+
+        let (min, max) = scene.bounds().unwrap();
+        assert_vec(&min, -0.5, -1.0, -0.5);
+        assert_vec(&max, 3.0, 3.0, 6.0);
+    }
+
+    #[test]
+    fn with_mesh() {
+        let scene = Scene::with_mesh(shape::cube(2.0));
+        assert_eq!((scene.meshes.len(), scene.nodes.len(), scene.roots.len()), (1, 1, 1));
+        let (min, max) = scene.bounds().unwrap();
+        assert_vec(&min, -1.0, -1.0, -1.0);
+        assert_vec(&max, 1.0, 1.0, 1.0);
+    }
+}
@@ -0,0 +1,43 @@
+//! Helpers shared by the unit tests
+
+use std::path::{Path, PathBuf};
+
+use crate::maths::vec::Vec3d;
+
+/// Assert that each component of `v` is within 1e-5 of the one expected
+pub fn assert_vec(v: &Vec3d, x: f32, y: f32, z: f32) {
+    assert!((v.x - x).abs() < 1e-5 && (v.y - y).abs() < 1e-5 && (v.z - z).abs() < 1e-5, "expected ({}, {}, {}), got ({}, {}, {})", x, y, z, v.x, v.y, v.z);
+}
+
+/// A path in the temporary directory, unique to this process. Whatever is there, file or directory, is removed on drop
+pub struct TempPath(PathBuf);
+
+impl TempPath {
+    /// Reserve a path ending in `name`, which may have an extension. Names must be unique among the tests
+    pub fn new(name: &str) -> TempPath {
+        TempPath(std::env::temp_dir().join(format!("{}_{}", std::process::id(), name)))
+    }
+
+    /// Reserve a path and write `contents` to it
+    pub fn with_contents(name: &str, contents: impl AsRef<[u8]>) -> TempPath {
+        let path = TempPath::new(name);
+        std::fs::write(&path.0, contents).unwrap();
+        path
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Get the path as a string, as the file parsers take it
+    pub fn name(&self) -> String {
+        self.0.to_string_lossy().to_string()
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        // Not every test creates its file, so ignore errors
+        let _ = if self.0.is_dir() { std::fs::remove_dir_all(&self.0) } else { std::fs::remove_file(&self.0) };
+    }
+}
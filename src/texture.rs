@@ -0,0 +1,146 @@
+use std::fs::File;
+use std::io::BufReader;
+
+use colorsys::Rgb;
+
+/// How a texture is sampled between the centres of its texels
+#[derive(Clone, Copy, PartialEq)]
+pub enum Filter {
+    /// Use the texel the point falls in, giving sharp, blocky magnification
+    Nearest,
+    /// Blend the four texels nearest the point by their distance from it
+    Bilinear,
+}
+
+/// Texture coordinates `(u, v)`
+pub type Uv = (f32, f32);
+
+/// An image applied to faces, addressed by texture coordinates. `u` runs left to right and `v` bottom to top, each from 0 to 1,
+/// and coordinates outside that range repeat the image
+pub struct Texture {
+    width: usize,
+    height: usize,
+    /// RGB bytes of each texel, row by row from the top
+    texels: Vec<[u8; 3]>,
+}
+
+impl Texture {
+    /// Construct a texture from RGB texels, row by row from the top. Return None if there are not `width * height` of them
+    pub fn new(width: usize, height: usize, texels: Vec<[u8; 3]>) -> Option<Texture> {
+        if width == 0 || height == 0 || texels.len() != width * height {
+            return None;
+        }
+        Some(Texture { width, height, texels })
+    }
+
+    /// Get width in texels
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Get height in texels
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Get the texel at the given column and row, wrapping around each edge
+    fn texel(&self, x: isize, y: isize) -> [f32; 3] {
+        let x = x.rem_euclid(self.width as isize) as usize;
+        let y = y.rem_euclid(self.height as isize) as usize;
+        self.texels[y * self.width + x].map(|c| c as f32)
+    }
+
+    /// Get the color at the given texture coordinates
+    pub fn sample(&self, u: f32, v: f32, filter: Filter) -> Rgb {
+        // Position in texels, measured from the top left corner
+        let x = u * self.width as f32;
+        let y = (1.0 - v) * self.height as f32;
+        let [r, g, b] = match filter {
+            Filter::Nearest => self.texel(x.floor() as isize, y.floor() as isize),
+            Filter::Bilinear => {
+                // Texel centres lie half way across each texel
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (x0, y0) = (x.floor(), y.floor());
+                let (fx, fy) = (x - x0, y - y0);
+                let (x0, y0) = (x0 as isize, y0 as isize);
+                let lerp = |a: [f32; 3], b: [f32; 3], t: f32| [0, 1, 2].map(|i| a[i] + (b[i] - a[i]) * t);
+                let top = lerp(self.texel(x0, y0), self.texel(x0 + 1, y0), fx);
+                let bottom = lerp(self.texel(x0, y0 + 1), self.texel(x0 + 1, y0 + 1), fx);
+                lerp(top, bottom, fy)
+            }
+        };
+        Rgb::new(r as f64, g as f64, b as f64, None)
+    }
+}
+
+/// Parse a PNG image. Any alpha channel is discarded
+pub fn parse_file(filename: String) -> Result<Texture, png::DecodingError> {
+    let mut decoder = png::Decoder::new(BufReader::new(File::open(&filename)?));
+    // Expand palettes and low bit depths, and reduce 16-bit channels, so every channel is one byte
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let mut bytes = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut bytes)?;
+    bytes.truncate(info.buffer_size());
+
+    // Palettes were expanded, so each texel is grey or RGB, either of which may be followed by alpha
+    let texels = bytes
+        .chunks_exact(info.color_type.samples())
+        .map(|p| if p.len() < 3 { [p[0], p[0], p[0]] } else { [p[0], p[1], p[2]] })
+        .collect();
+    Ok(Texture { width: info.width as usize, height: info.height as usize, texels })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempPath;
+
+    /// Red and green on the top row, blue and white on the bottom
+    fn quad() -> Texture {
+        Texture::new(2, 2, vec![[255, 0, 0], [0, 255, 0], [0, 0, 255], [255, 255, 255]]).unwrap()
+    }
+
+    fn assert_rgb(rgb: &Rgb, r: f64, g: f64, b: f64) {
+        assert!((rgb.red() - r).abs() < 1e-3 && (rgb.green() - g).abs() < 1e-3 && (rgb.blue() - b).abs() < 1e-3, "{:?} != ({}, {}, {})", rgb, r, g, b);
+    }
+
+    #[test]
+    fn nearest() {
+        let texture = quad();
+        assert_rgb(&texture.sample(0.1, 0.9, Filter::Nearest), 255.0, 0.0, 0.0);
+        assert_rgb(&texture.sample(0.9, 0.9, Filter::Nearest), 0.0, 255.0, 0.0);
+        assert_rgb(&texture.sample(0.1, 0.1, Filter::Nearest), 0.0, 0.0, 255.0);
+        assert_rgb(&texture.sample(0.9, 0.1, Filter::Nearest), 255.0, 255.0, 255.0);
+        // Coordinates outside 0 to 1 repeat
+        assert_rgb(&texture.sample(1.1, -0.1, Filter::Nearest), 255.0, 0.0, 0.0);
+        assert_rgb(&texture.sample(-0.1, 2.1, Filter::Nearest), 255.0, 255.0, 255.0);
+    }
+
+    #[test]
+    fn bilinear() {
+        let texture = quad();
+        // At a texel's centre, only that texel counts
+        assert_rgb(&texture.sample(0.25, 0.75, Filter::Bilinear), 255.0, 0.0, 0.0);
+        // Half way between the top two texels
+        assert_rgb(&texture.sample(0.5, 0.75, Filter::Bilinear), 127.5, 127.5, 0.0);
+        // The middle of the image blends all four
+        assert_rgb(&texture.sample(0.5, 0.5, Filter::Bilinear), 127.5, 127.5, 127.5);
+        // Blending wraps around the edges
+        assert_rgb(&texture.sample(0.0, 0.75, Filter::Bilinear), 127.5, 127.5, 0.0);
+    }
+
+    #[test]
+    fn png() {
+        let path = TempPath::new("texture.png");
+        let mut encoder = png::Encoder::new(File::create(path.path()).unwrap(), 2, 1);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header().unwrap().write_image_data(&[10, 20, 30, 255, 40, 50, 60, 0]).unwrap();
+
+        let texture = parse_file(path.name()).unwrap();
+        assert_eq!((texture.width(), texture.height()), (2, 1));
+        assert_rgb(&texture.sample(0.25, 0.5, Filter::Nearest), 10.0, 20.0, 30.0);
+        assert_rgb(&texture.sample(0.75, 0.5, Filter::Nearest), 40.0, 50.0, 60.0);
+    }
+}
@@ -0,0 +1,475 @@
+use std::rc::Rc;
+
+use crate::lighting::{linear_to_srgb, srgb_to_linear, Illumination, Intensity, Material};
+use crate::maths::vec::{Vec3d, Vec4d};
+use crate::mesh::Shading;
+use crate::texture::{Filter, Texture, Uv};
+use colorsys::Rgb;
+
+pub struct Triangle {
+    /// Vertices of the triangle. Defined in a clockwise orientation
+    pub vertices: (Vec3d, Vec3d, Vec3d),
+    // Fill color of the triangle. If nothing, no fill
+    pub fill: Option<Rgb>,
+    // Stroke color of the triangle. If nothing, no stroke
+    pub stroke: Option<Rgb>,
+    // Light reflected by the triangle
+    pub light: Illumination,
+    /// Color at each vertex, in the same order as `vertices`. If present (and there is a fill), these are blended across the triangle instead of `fill`
+    pub colors: Option<(Rgb, Rgb, Rgb)>,
+    /// Unit normal at each vertex, in the same order as `vertices`
+    pub normals: Option<(Vec3d, Vec3d, Vec3d)>,
+    /// How to shade the triangle. If nothing, the renderer decides
+    pub shading: Option<Shading>,
+    /// Light reflected by each vertex, for smooth shading. If present, these are blended across the triangle instead of `light`
+    pub lights: Option<(Illumination, Illumination, Illumination)>,
+    /// Position of each vertex in world space, for per-pixel lighting. If present along with `normals`, each pixel is lit separately
+    pub world: Option<(Vec3d, Vec3d, Vec3d)>,
+    /// How the surface responds to light
+    pub material: Material,
+    /// Texture coordinates of each vertex, in the same order as `vertices`
+    pub uvs: Option<(Uv, Uv, Uv)>,
+    /// Image to fill the triangle with, in place of `fill` and `colors`. Only used if there are `uvs`
+    pub texture: Option<Rc<Texture>>,
+    /// How `texture` is sampled
+    pub filter: Filter,
+    /// Position of each vertex in clip space, between projection and the perspective divide. If present, the triangle is clipped in clip space
+    pub clip: Option<(Vec4d, Vec4d, Vec4d)>,
+    /// Reciprocal of each vertex's depth in view space, once projected. If present, vertex data is interpolated with perspective correction
+    pub inv_w: Option<(f32, f32, f32)>,
+}
+
+/// Blend three colors using barycentric weights. Channels are blended in linear space, as in `Triangle::shade`
+fn blend(colors: &(Rgb, Rgb, Rgb), w: [f32; 3]) -> Rgb {
+    let (a, b, c) = colors;
+    let channel = |a: f64, b: f64, c: f64| {
+        let linear = |c: f64| srgb_to_linear(c as f32 / 255.0);
+        (linear_to_srgb(linear(a) * w[0] + linear(b) * w[1] + linear(c) * w[2]) * 255.0) as f64
+    };
+    Rgb::new(
+        channel(a.red(), b.red(), c.red()),
+        channel(a.green(), b.green(), c.green()),
+        channel(a.blue(), b.blue(), c.blue()),
+        None,
+    )
+}
+
+/// Blend three vectors using barycentric weights
+fn blend_vec(v: &(Vec3d, Vec3d, Vec3d), w: [f32; 3]) -> Vec3d {
+    v.0 * w[0] + v.1 * w[1] + v.2 * w[2]
+}
+
+/// Blend three texture coordinates using barycentric weights
+fn blend_uv(uvs: &(Uv, Uv, Uv), w: [f32; 3]) -> Uv {
+    let (a, b, c) = uvs;
+    (a.0 * w[0] + b.0 * w[1] + c.0 * w[2], a.1 * w[0] + b.1 * w[1] + c.1 * w[2])
+}
+
+/// Blend three lights using barycentric weights
+fn blend_light(v: &(Illumination, Illumination, Illumination), w: [f32; 3]) -> Illumination {
+    v.0 * w[0] + v.1 * w[1] + v.2 * w[2]
+}
+
+impl Clone for Triangle {
+    fn clone(&self) -> Self {
+        Triangle {
+            vertices: (
+                self.vertices.0,
+                self.vertices.1,
+                self.vertices.2,
+            ),
+            fill: self.fill.clone(),
+            stroke: self.stroke.clone(),
+            light: self.light,
+            colors: self.colors.clone(),
+            normals: self.normals,
+            shading: self.shading,
+            lights: self.lights,
+            world: self.world,
+            material: self.material,
+            uvs: self.uvs,
+            texture: self.texture.clone(),
+            filter: self.filter,
+            clip: self.clip,
+            inv_w: self.inv_w,
+        }
+    }
+}
+
+impl Triangle {
+    /// Construct a new triangle
+    pub fn new(a: Vec3d, b: Vec3d, c: Vec3d) -> Triangle {
+        Triangle {
+            vertices: (a, b, c),
+            fill: Some(Rgb::new(255.0, 255.0, 255.0, None)),
+            stroke: Some(Rgb::new(0.0, 0.0, 0.0, None)),
+            light: Intensity::grey(1.0).into(),
+            colors: None,
+            normals: None,
+            shading: None,
+            lights: None,
+            world: None,
+            material: Material::default(),
+            uvs: None,
+            texture: None,
+            filter: Filter::Bilinear,
+            clip: None,
+            inv_w: None,
+        }
+    }
+
+    /// Get normal vector
+    pub fn normal(&mut self) -> Vec3d {
+        let v1 = self.vertices.1 - self.vertices.0;
+        let v2 = self.vertices.2 - self.vertices.0;
+        Vec3d::normal(&v1, &v2)
+    }
+
+    /// Link together a square face of vertices
+    pub fn link_sq(a: &Vec3d, b: &Vec3d, c: &Vec3d, d: &Vec3d) -> Vec<Triangle> {
+        vec![
+            Triangle::new(*a, *b, *c),
+            Triangle::new(*a, *c, *d),
+        ]
+    }
+
+    /// Get avg `z` component
+    pub fn mid_z(&self) -> f32 {
+        (self.vertices.0.z + self.vertices.1.z + self.vertices.2.z) / 3.0
+    }
+
+    /// Clip triangle against a plane, keeping the side on which the plane's dot product with each point is not negative (see `Vec4d::plane`).
+    /// Points are taken from `clip` if present, otherwise from `vertices` with `w` = 1.
+    /// Return the 0, 1 or 2 triangles which remain inside the plane. Vertex order is preserved.
+    pub fn clip_against_plane(&self, plane: &Vec4d) -> Vec<Triangle> {
+        let points = match self.clip {
+            Some((a, b, c)) => [a, b, c],
+            None => [&self.vertices.0, &self.vertices.1, &self.vertices.2].map(Vec4d::from_point),
+        };
+        let dist = points.map(|p| Vec4d::dot_product(plane, &p));
+        let corners = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+        // Walk the edges, keeping inside points and adding a point wherever an edge crosses the plane.
+        // Each point is kept as its barycentric weights within this triangle, so the position and other vertex data can be interpolated
+        let mut poly: Vec<[f32; 3]> = Vec::with_capacity(4);
+        for i in 0..3 {
+            let (dc, dn) = (dist[i], dist[(i + 1) % 3]);
+            if dc >= 0.0 {
+                poly.push(corners[i]);
+            }
+            if (dc > 0.0 && dn < 0.0) || (dc < 0.0 && dn > 0.0) {
+                let t = dc / (dc - dn);
+                let (a, b) = (corners[i], corners[(i + 1) % 3]);
+                poly.push([a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t, a[2] + (b[2] - a[2]) * t]);
+            }
+        }
+
+        // Triangulate the resulting polygon (3 or 4 points) as a fan
+        let mut tris = Vec::new();
+        for i in 1..poly.len().saturating_sub(1) {
+            tris.push(self.sub_triangle([&poly[0], &poly[i], &poly[i + 1]]));
+        }
+        tris
+    }
+
+    /// Construct a triangle lying within this one, with the same style, from the barycentric weights of its vertices within this triangle
+    fn sub_triangle(&self, points: [&[f32; 3]; 3]) -> Triangle {
+        let mut tri = self.clone();
+        // Positions are linear across the triangle as it is, so use the weights as they are
+        tri.vertices = (blend_vec(&self.vertices, *points[0]), blend_vec(&self.vertices, *points[1]), blend_vec(&self.vertices, *points[2]));
+        if let Some((a, b, c)) = self.clip {
+            let blend = |w: &[f32; 3]| a * w[0] + b * w[1] + c * w[2];
+            tri.clip = Some((blend(points[0]), blend(points[1]), blend(points[2])));
+        }
+        let w = points.map(|w| self.perspective(*w));
+        if let Some(colors) = &self.colors {
+            tri.colors = Some((blend(colors, w[0]), blend(colors, w[1]), blend(colors, w[2])));
+        }
+        if let Some(normals) = &self.normals {
+            tri.normals = Some((blend_vec(normals, w[0]), blend_vec(normals, w[1]), blend_vec(normals, w[2])));
+        }
+        if let Some(world) = &self.world {
+            tri.world = Some((blend_vec(world, w[0]), blend_vec(world, w[1]), blend_vec(world, w[2])));
+        }
+        if let Some(lights) = &self.lights {
+            tri.lights = Some((blend_light(lights, w[0]), blend_light(lights, w[1]), blend_light(lights, w[2])));
+        }
+        if let Some(uvs) = &self.uvs {
+            tri.uvs = Some((blend_uv(uvs, w[0]), blend_uv(uvs, w[1]), blend_uv(uvs, w[2])));
+        }
+        // Reciprocal depth is linear across the projected triangle, so uses the weights as they are
+        if let Some((a, b, c)) = self.inv_w {
+            let blend = |w: &[f32; 3]| a * w[0] + b * w[1] + c * w[2];
+            tri.inv_w = Some((blend(points[0]), blend(points[1]), blend(points[2])));
+        }
+        tri
+    }
+
+    /// Convert barycentric weights in screen space to weights across the triangle before it was projected, so vertex data
+    /// interpolated with them is perspective correct. Weights are returned as they are if the triangle has not been projected
+    pub fn perspective(&self, w: [f32; 3]) -> [f32; 3] {
+        match self.inv_w {
+            Some((a, b, c)) => {
+                let p = [w[0] * a, w[1] * b, w[2] * c];
+                let sum = p[0] + p[1] + p[2];
+                if sum > 0.0 { p.map(|p| p / sum) } else { w }
+            }
+            None => w,
+        }
+    }
+
+    /// Get the unshaded fill color at the point with the given barycentric weights. If nothing, no fill
+    pub fn fill_at(&self, w: [f32; 3]) -> Option<Rgb> {
+        if let (Some(texture), Some(uvs)) = (&self.texture, &self.uvs) {
+            let (u, v) = blend_uv(uvs, w);
+            return Some(texture.sample(u, v, self.filter));
+        }
+        match (&self.fill, &self.colors) {
+            (Some(_), Some(colors)) => Some(blend(colors, w)),
+            (fill, _) => fill.clone(),
+        }
+    }
+
+    /// Get the position and unit normal in world space at the point with the given barycentric weights, if the triangle is lit per pixel
+    pub fn surface_at(&self, w: [f32; 3]) -> Option<(Vec3d, Vec3d)> {
+        match (&self.world, &self.normals) {
+            (Some(world), Some(normals)) => Some((blend_vec(world, w), blend_vec(normals, w).normalise())),
+            _ => None,
+        }
+    }
+
+    /// Get the light at the point with the given barycentric weights, ignoring per-pixel lighting
+    pub fn light_at(&self, w: [f32; 3]) -> Illumination {
+        self.lights.as_ref().map_or(self.light, |lights| blend_light(lights, w))
+    }
+
+    /// Apply light to a color. The color is decoded to linear RGB, multiplied by the diffuse light in each channel and the specular
+    /// light added, then encoded back to sRGB. Channels lit beyond full brightness are clamped
+    pub fn shade(rgb: &Rgb, light: &Illumination) -> Rgb {
+        let channel = |c: f64, diffuse: f32, specular: f32| {
+            (linear_to_srgb((srgb_to_linear(c as f32 / 255.0) * diffuse + specular).clamp(0.0, 1.0)) * 255.0) as f64
+        };
+        let (d, s) = (&light.diffuse, &light.specular);
+        Rgb::new(channel(rgb.red(), d.r, s.r), channel(rgb.green(), d.g, s.g), channel(rgb.blue(), d.b, s.b), None)
+    }
+
+    /// Get fill color. Vertex colors, lights and textures are taken at the centre
+    pub fn get_fill(&mut self) -> Option<Rgb> {
+        let centre = [1.0 / 3.0; 3];
+        let rgb = self.fill_at(centre)?;
+        Some(Triangle::shade(&rgb, &self.light_at(centre)))
+    }
+
+    /// Get stroke color
+    pub fn get_stroke(&mut self) -> Option<Rgb> {
+        self.stroke.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::assert_vec;
+
+    fn near_plane() -> Vec4d {
+        Vec4d::plane(&Vec3d::new(0.0, 0.0, 1.0), &Vec3d::unit_z())
+    }
+
+    fn styled(a: Vec3d, b: Vec3d, c: Vec3d) -> Triangle {
+        let mut tri = Triangle::new(a, b, c);
+        tri.fill = Some(Rgb::new(10.0, 20.0, 30.0, None));
+        tri.stroke = None;
+        tri.light = Intensity::grey(0.5).into();
+        tri
+    }
+
+    fn assert_style(tri: &Triangle) {
+        let fill = tri.fill.clone().unwrap();
+        assert_eq!((fill.red(), fill.green(), fill.blue()), (10.0, 20.0, 30.0));
+        assert!(tri.stroke.is_none());
+        assert_eq!(tri.light, Intensity::grey(0.5).into());
+    }
+
+    #[test]
+    fn clip_edge_intersection() {
+        // Both edges leaving the first vertex cross the plane half way along
+        let tri = styled(Vec3d::new(0.0, 0.0, 0.0), Vec3d::new(2.0, 4.0, 2.0), Vec3d::new(2.0, 0.0, 2.0));
+        let out = tri.clip_against_plane(&near_plane());
+        assert_eq!(out.len(), 2);
+        assert_vec(&out[0].vertices.0, 1.0, 2.0, 1.0);
+        assert_vec(&out[1].vertices.2, 1.0, 0.0, 1.0);
+    }
+
+    #[test]
+    fn clip_all_inside() {
+        let plane = near_plane();
+        let tri = styled(Vec3d::new(0.0, 0.0, 2.0), Vec3d::new(0.0, 1.0, 3.0), Vec3d::new(1.0, 0.0, 4.0));
+        let out = tri.clip_against_plane(&plane);
+        assert_eq!(out.len(), 1);
+        assert_vec(&out[0].vertices.0, 0.0, 0.0, 2.0);
+        assert_vec(&out[0].vertices.1, 0.0, 1.0, 3.0);
+        assert_vec(&out[0].vertices.2, 1.0, 0.0, 4.0);
+        assert_style(&out[0]);
+    }
+
+    #[test]
+    fn clip_all_outside() {
+        let plane = near_plane();
+        let tri = styled(Vec3d::new(0.0, 0.0, 0.5), Vec3d::new(0.0, 1.0, -1.0), Vec3d::new(1.0, 0.0, 0.0));
+        assert!(tri.clip_against_plane(&plane).is_empty());
+    }
+
+    #[test]
+    fn clip_one_inside() {
+        let plane = near_plane();
+        let tri = styled(Vec3d::new(0.0, 0.0, 3.0), Vec3d::new(2.0, 0.0, -1.0), Vec3d::new(0.0, 2.0, -1.0));
+        let out = tri.clip_against_plane(&plane);
+        assert_eq!(out.len(), 1);
+        assert_vec(&out[0].vertices.0, 0.0, 0.0, 3.0);
+        assert_vec(&out[0].vertices.1, 1.0, 0.0, 1.0);
+        assert_vec(&out[0].vertices.2, 0.0, 1.0, 1.0);
+        assert_style(&out[0]);
+    }
+
+    #[test]
+    fn clip_two_inside() {
+        let plane = near_plane();
+        let tri = styled(Vec3d::new(0.0, 0.0, 3.0), Vec3d::new(2.0, 0.0, 3.0), Vec3d::new(0.0, 0.0, -1.0));
+        let out = tri.clip_against_plane(&plane);
+        assert_eq!(out.len(), 2);
+        assert_vec(&out[0].vertices.0, 0.0, 0.0, 3.0);
+        assert_vec(&out[0].vertices.1, 2.0, 0.0, 3.0);
+        assert_vec(&out[0].vertices.2, 1.0, 0.0, 1.0);
+        assert_vec(&out[1].vertices.0, 0.0, 0.0, 3.0);
+        assert_vec(&out[1].vertices.1, 1.0, 0.0, 1.0);
+        assert_vec(&out[1].vertices.2, 0.0, 0.0, 1.0);
+        for tri in &out {
+            assert_style(tri);
+        }
+    }
+
+    #[test]
+    fn clip_homogeneous() {
+        // Clip space positions decide what is clipped, and are blended along with the vertices
+        let mut tri = styled(Vec3d::new(0.0, 0.0, 0.0), Vec3d::new(4.0, 0.0, 0.0), Vec3d::new(0.0, 4.0, 0.0));
+        tri.clip = Some((Vec4d::new(0.0, 0.0, 0.0, 1.0), Vec4d::new(3.0, 0.0, 0.0, 1.0), Vec4d::new(0.0, 3.0, 0.0, 1.0)));
+        // Keep x <= w
+        let out = tri.clip_against_plane(&Vec4d::new(-1.0, 0.0, 0.0, 1.0));
+        assert_eq!(out.len(), 2);
+        let (a, b, c) = out[0].clip.unwrap();
+        assert_eq!((a, b), (Vec4d::new(0.0, 0.0, 0.0, 1.0), Vec4d::new(1.0, 0.0, 0.0, 1.0)));
+        assert!((c.x - 1.0).abs() < 1e-5 && (c.y - 2.0).abs() < 1e-5, "{:?}", c);
+        assert_vec(&out[0].vertices.1, 4.0 / 3.0, 0.0, 0.0);
+        assert_vec(&out[0].vertices.2, 4.0 / 3.0, 8.0 / 3.0, 0.0);
+        assert_style(&out[0]);
+    }
+
+    #[test]
+    fn clip_vertex_on_plane() {
+        let plane = near_plane();
+        let tri = styled(Vec3d::new(0.0, 0.0, 1.0), Vec3d::new(1.0, 0.0, 2.0), Vec3d::new(0.0, 1.0, 2.0));
+        assert_eq!(tri.clip_against_plane(&plane).len(), 1);
+
+        let tri = styled(Vec3d::new(0.0, 0.0, 1.0), Vec3d::new(1.0, 0.0, 0.0), Vec3d::new(0.0, 1.0, 0.0));
+        assert!(tri.clip_against_plane(&plane).is_empty());
+    }
+
+    fn channels(rgb: &Rgb) -> [f64; 3] {
+        [rgb.red(), rgb.green(), rgb.blue()]
+    }
+
+    #[test]
+    fn shade_white_light() {
+        // Full white light leaves any color unchanged, and no light gives black
+        for rgb in [Rgb::new(255.0, 255.0, 255.0, None), Rgb::new(200.0, 30.0, 90.0, None), Rgb::new(201.0, 201.0, 201.0, None)] {
+            let shaded = Triangle::shade(&rgb, &Intensity::grey(1.0).into());
+            for (a, b) in channels(&shaded).iter().zip(channels(&rgb)) {
+                assert!((a - b).abs() < 0.01, "{:?} != {:?}", channels(&shaded), channels(&rgb));
+            }
+            assert_eq!(channels(&Triangle::shade(&rgb, &Intensity::grey(0.0).into())), [0.0; 3]);
+        }
+    }
+
+    #[test]
+    fn shade_linear() {
+        // Half the light is half the linear value, not half the sRGB value
+        let shaded = Triangle::shade(&Rgb::new(255.0, 0.0, 0.0, None), &Intensity::grey(0.5).into());
+        assert!((shaded.red() - 255.0 * linear_to_srgb(0.5) as f64).abs() < 0.01);
+        assert_eq!((shaded.green(), shaded.blue()), (0.0, 0.0));
+        // Saturated colors keep their hue
+        let shaded = Triangle::shade(&Rgb::new(0.0, 128.0, 255.0, None), &Intensity::grey(0.25).into());
+        assert!(shaded.red() == 0.0 && shaded.green() > 0.0 && shaded.green() < shaded.blue());
+        // Colored light tints each channel separately, and overbright channels clamp
+        let shaded = Triangle::shade(&Rgb::new(255.0, 255.0, 255.0, None), &Intensity::new(2.0, 1.0, 0.0).into());
+        assert!((shaded.red() - 255.0).abs() < 0.01 && (shaded.green() - 255.0).abs() < 0.01 && shaded.blue() == 0.0);
+    }
+
+    #[test]
+    fn shade_specular() {
+        // Highlights are added after the color is applied, so a white highlight shows on a red surface
+        let light = Illumination::new(Intensity::grey(0.5), Intensity::grey(0.5));
+        let shaded = Triangle::shade(&Rgb::new(255.0, 0.0, 0.0, None), &light);
+        assert!((shaded.red() - 255.0).abs() < 0.01);
+        assert!((shaded.green() - 255.0 * linear_to_srgb(0.5) as f64).abs() < 0.01 && shaded.green() == shaded.blue());
+    }
+
+    #[test]
+    fn shade_continuous() {
+        // Sweep every grey and a range of lights: neighbouring inputs must give neighbouring, non-decreasing outputs
+        for light in [0.1, 0.5, 0.9, 1.0, 1.5] {
+            let mut last: Option<[f64; 3]> = None;
+            for i in 0..=255 {
+                let c = i as f64;
+                let shaded = channels(&Triangle::shade(&Rgb::new(c, c, c, None), &Intensity::grey(light).into()));
+                if let Some(last) = last {
+                    for (a, b) in shaded.iter().zip(last) {
+                        assert!(*a >= b && a - b < 4.0, "light {}: {} -> {} at {}", light, b, a, i);
+                    }
+                }
+                last = Some(shaded);
+            }
+        }
+        // Likewise for a color under a steadily brightening light, from none to overbright
+        for i in 0..1000 {
+            let (a, b) = (i as f32 / 500.0, (i + 1) as f32 / 500.0);
+            let rgb = Rgb::new(210.0, 120.0, 40.0, None);
+            let (sa, sb) = (channels(&Triangle::shade(&rgb, &Intensity::grey(a).into())), channels(&Triangle::shade(&rgb, &Intensity::grey(b).into())));
+            for (x, y) in sa.iter().zip(sb) {
+                assert!(y >= *x && y - x < 10.0, "{} -> {} between lights {} and {}", x, y, a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn blend_linear() {
+        // Half way between red and green has half the linear value of each, not half the sRGB value
+        let colors = (Rgb::new(255.0, 0.0, 0.0, None), Rgb::new(0.0, 255.0, 0.0, None), Rgb::new(200.0, 30.0, 90.0, None));
+        let half = 255.0 * linear_to_srgb(0.5) as f64;
+        let mid = channels(&blend(&colors, [0.5, 0.5, 0.0]));
+        assert!((mid[0] - half).abs() < 0.01 && (mid[1] - half).abs() < 0.01 && mid[2] == 0.0, "{:?}", mid);
+        // Each corner keeps its own color
+        for (a, b) in channels(&blend(&colors, [0.0, 0.0, 1.0])).iter().zip(channels(&colors.2)) {
+            assert!((a - b).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn perspective_weights() {
+        // The second vertex is four times as deep as the first, so half way between them on screen is a fifth of the way in the scene
+        let mut tri = styled(Vec3d::new(0.0, 0.0, 0.5), Vec3d::new(4.0, 0.0, 0.5), Vec3d::new(0.0, 4.0, 0.5));
+        let w = tri.perspective([0.5, 0.5, 0.0]);
+        assert_eq!(w, [0.5, 0.5, 0.0]);
+        tri.inv_w = Some((1.0, 0.25, 0.25));
+        let w = tri.perspective([0.5, 0.5, 0.0]);
+        assert!((w[0] - 0.8).abs() < 1e-5 && (w[1] - 0.2).abs() < 1e-5 && w[2] == 0.0, "{:?}", w);
+
+        // Clipping in screen space gives new vertices perspective correct texture coordinates, and linearly blended depths
+        tri.uvs = Some(((0.0, 0.0), (1.0, 0.0), (0.0, 1.0)));
+        let out = tri.clip_against_plane(&Vec4d::plane(&Vec3d::new(2.0, 0.0, 0.0), &Vec3d::new(-1.0, -1.0, 0.0)));
+        assert_eq!(out.len(), 1);
+        let (a, b, _) = out[0].uvs.unwrap();
+        assert_eq!(a, (0.0, 0.0));
+        assert!((b.0 - 0.2).abs() < 1e-5 && b.1.abs() < 1e-5, "{:?}", b);
+        assert!((out[0].inv_w.unwrap().1 - 0.625).abs() < 1e-5);
+    }
+}
@@ -1,2 +0,0 @@
-pub mod mat;
-pub mod vec;
\ No newline at end of file
@@ -1,3 +0,0 @@
-pub mod maths;
-pub mod mesh;
-pub mod triangle;
@@ -0,0 +1,344 @@
+//! The interactive window, built with the `window` feature
+
+use std::collections::HashSet;
+
+use ggez::conf::{WindowMode, WindowSetup};
+use ggez::event::{self, EventHandler, MouseButton};
+use ggez::graphics::{self, Color};
+use ggez::input::keyboard::{KeyCode, KeyInput};
+use ggez::{Context, ContextBuilder, GameResult};
+use ggez::glam::*;
+
+use colorsys::Rgb;
+use threed_renderer::lighting::{Light, Reflection};
+use threed_renderer::{pipeline, raster};
+use threed_renderer::texture::Filter;
+use threed_renderer::{Camera, Framebuffer, Mat4x4, OrbitCamera, Pipeline, Quat, Scene, Shading, Vec3d, Viewpoint};
+
+use crate::{WIN_HEIGHT, WIN_WIDTH};
+
+/// Camera movement speed, in units per second
+const MOVE_SPEED: f32 = 4.0;
+/// Camera turn speed using the arrow keys, in radians per second
+const TURN_SPEED: f32 = 1.5;
+/// Camera turn per pixel of mouse movement, in radians
+const MOUSE_SENSITIVITY: f32 = 0.005;
+/// Orbit camera pan per pixel of mouse movement, as a fraction of its distance from the target
+const PAN_SENSITIVITY: f32 = 0.002;
+/// Orbit camera distance multiplier per scroll wheel step
+const ZOOM_STEP: f32 = 0.9;
+/// Distance from the first-person camera's starting position to the spinning mesh
+const SPIN_DISTANCE: f32 = 5.0;
+
+/// Open a window showing `scene`, if any, and run until it is closed. If `lights` is empty, a single white light shines from the
+/// initial camera position
+pub fn run(scene: Option<Scene>, lights: Vec<Light>, filter: Filter, orthographic: bool) {
+    // Make a Context.
+    let cb = ContextBuilder::new("3D Renderer", "triflicAcid")
+        .window_setup(WindowSetup::default().title("3D Renderer"))
+        .window_mode(WindowMode::default().dimensions(WIN_WIDTH, WIN_HEIGHT).resizable(true));
+    let (mut ctx, event_loop) = cb.build().expect("Could not create ggez context!");
+    let mut rd = Renderer::new(&mut ctx);
+    rd.filter = filter;
+    rd.orthographic = orthographic;
+    rd.update_projection();
+    if !lights.is_empty() {
+        rd.lights = lights;
+    }
+
+    if let Some(scene) = scene {
+        rd.scene = scene;
+        rd.fit_orbit();
+    }
+
+    event::run(ctx, event_loop, rd);
+}
+
+/// Convert a color to ggez's, which is 0 to 1 in each channel
+fn to_color(rgb: &Rgb) -> Color {
+    Color::new((rgb.red() / 255.0) as f32, (rgb.green() / 255.0) as f32, (rgb.blue() / 255.0) as f32, 1.0)
+}
+
+/// How the view is controlled
+#[derive(PartialEq)]
+enum ViewMode {
+    /// The mesh spins automatically, and the first-person camera can fly around it
+    Spin,
+    /// The mesh is still, and the orbit camera circles it
+    Orbit,
+}
+
+/// How triangles are drawn to the screen
+#[derive(PartialEq)]
+enum RenderMode {
+    /// Sort triangles back to front by their average depth, and draw them in order
+    Painter,
+    /// Rasterize triangles in software, using a per-pixel depth buffer
+    DepthBuffer,
+}
+
+struct Renderer {
+    scene: Scene,
+    theta: f32,
+    /// Lights fixed in the world
+    lights: Vec<Light>,
+    /// Add a light travelling from the camera in the direction it faces
+    headlight: bool,
+    camera: Camera,
+    orbit: OrbitCamera,
+    mode: ViewMode,
+    render_mode: RenderMode,
+    shading: Shading,
+    reflection: Reflection,
+    filter: Filter,
+    /// Use an orthographic projection rather than perspective
+    orthographic: bool,
+    /// Projection for the window's current size
+    proj: Mat4x4,
+    /// Window width in pixels
+    width: f32,
+    /// Window height in pixels
+    height: f32,
+    framebuffer: Framebuffer,
+    held_keys: HashSet<KeyCode>,
+    dragging: Option<MouseButton>,
+}
+
+impl Renderer {
+    pub fn new(_ctx: &mut Context) -> Renderer {
+        Renderer {
+            scene: Scene::new(),
+            lights: vec![Light::directional(&Vec3d::unit_z())],
+            headlight: false,
+            camera: Camera::new(Vec3d::origin()),
+            orbit: OrbitCamera::new(Vec3d::origin(), 5.0),
+            mode: ViewMode::Orbit,
+            render_mode: RenderMode::DepthBuffer,
+            shading: Shading::Flat,
+            reflection: Reflection::BlinnPhong,
+            filter: Filter::Bilinear,
+            orthographic: false,
+            proj: pipeline::perspective(WIN_WIDTH, WIN_HEIGHT),
+            width: WIN_WIDTH,
+            height: WIN_HEIGHT,
+            framebuffer: Framebuffer::new(WIN_WIDTH as usize, WIN_HEIGHT as usize),
+            theta: 0.0,
+            held_keys: HashSet::new(),
+            dragging: None,
+        }
+    }
+
+    /// Point the orbit camera at the centre of the scene, far enough away to see all of it
+    pub fn fit_orbit(&mut self) {
+        if let Some((min, max)) = self.scene.bounds() {
+            self.orbit.fit(&min, &max, pipeline::FOV.to_radians());
+        }
+        self.update_projection();
+    }
+
+    /// Rebuild the projection for the window's size. An orthographic projection shows as much as the perspective one does at the mesh
+    pub fn update_projection(&mut self) {
+        self.proj = if self.orthographic {
+            let distance = match self.mode {
+                ViewMode::Spin => SPIN_DISTANCE,
+                ViewMode::Orbit => self.orbit.distance,
+            };
+            pipeline::orthographic(distance, self.width, self.height)
+        } else {
+            pipeline::perspective(self.width, self.height)
+        };
+    }
+}
+
+impl EventHandler for Renderer {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        if self.mode == ViewMode::Orbit {
+            return Ok(());
+        }
+
+        self.theta = self.theta + 0.02; // % (2.0 * std::f32::consts::PI);
+
+        // Move camera using held keys
+        let dt = ctx.time.delta().as_secs_f32();
+        let held = |key| self.held_keys.contains(&key);
+        let axis = |pos, neg| (held(pos) as i32 - held(neg) as i32) as f32;
+        let forward = axis(KeyCode::W, KeyCode::S) * MOVE_SPEED * dt;
+        let strafe = axis(KeyCode::D, KeyCode::A) * MOVE_SPEED * dt;
+        let rise = axis(KeyCode::Space, KeyCode::LShift) * MOVE_SPEED * dt;
+        let yaw = axis(KeyCode::Right, KeyCode::Left) * TURN_SPEED * dt;
+        let pitch = axis(KeyCode::Up, KeyCode::Down) * TURN_SPEED * dt;
+
+        self.camera.move_forward(forward);
+        self.camera.strafe(strafe);
+        self.camera.rise(rise);
+        self.camera.turn(yaw, pitch);
+        Ok(())
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput, _repeated: bool) -> GameResult {
+        if let Some(key) = input.keycode {
+            match key {
+                KeyCode::Escape => ctx.request_quit(),
+                KeyCode::Tab => {
+                    self.mode = match self.mode {
+                        ViewMode::Spin => ViewMode::Orbit,
+                        ViewMode::Orbit => ViewMode::Spin,
+                    };
+                    self.update_projection();
+                }
+                KeyCode::P => {
+                    self.orthographic = !self.orthographic;
+                    self.update_projection();
+                }
+                KeyCode::Z => {
+                    self.render_mode = match self.render_mode {
+                        RenderMode::Painter => RenderMode::DepthBuffer,
+                        RenderMode::DepthBuffer => RenderMode::Painter,
+                    }
+                }
+                KeyCode::G => {
+                    self.shading = match self.shading {
+                        Shading::Flat => Shading::Smooth,
+                        Shading::Smooth => Shading::PerPixel,
+                        Shading::PerPixel => Shading::Flat,
+                    }
+                }
+                KeyCode::T => {
+                    self.filter = match self.filter {
+                        Filter::Nearest => Filter::Bilinear,
+                        Filter::Bilinear => Filter::Nearest,
+                    }
+                }
+                KeyCode::L => self.headlight = !self.headlight,
+                KeyCode::B => {
+                    self.reflection = match self.reflection {
+                        Reflection::Phong => Reflection::BlinnPhong,
+                        Reflection::BlinnPhong => Reflection::Phong,
+                    }
+                }
+                _ => (),
+            }
+            self.held_keys.insert(key);
+        }
+        Ok(())
+    }
+
+    fn key_up_event(&mut self, _ctx: &mut Context, input: KeyInput) -> GameResult {
+        if let Some(key) = input.keycode {
+            self.held_keys.remove(&key);
+        }
+        Ok(())
+    }
+
+    fn mouse_button_down_event(&mut self, _ctx: &mut Context, button: MouseButton, _x: f32, _y: f32) -> GameResult {
+        self.dragging = Some(button);
+        Ok(())
+    }
+
+    fn mouse_button_up_event(&mut self, _ctx: &mut Context, button: MouseButton, _x: f32, _y: f32) -> GameResult {
+        if self.dragging == Some(button) {
+            self.dragging = None;
+        }
+        Ok(())
+    }
+
+    fn mouse_motion_event(&mut self, _ctx: &mut Context, _x: f32, _y: f32, dx: f32, dy: f32) -> GameResult {
+        match (&self.mode, self.dragging) {
+            // Look around while the left button is held
+            (ViewMode::Spin, Some(MouseButton::Left)) => {
+                self.camera.turn(dx * MOUSE_SENSITIVITY, -dy * MOUSE_SENSITIVITY);
+            }
+            // Rotate around the target with the left button, pan with the middle button
+            (ViewMode::Orbit, Some(MouseButton::Left)) => {
+                self.orbit.orbit(dx * MOUSE_SENSITIVITY, -dy * MOUSE_SENSITIVITY);
+            }
+            (ViewMode::Orbit, Some(MouseButton::Middle)) => {
+                let k = self.orbit.distance * PAN_SENSITIVITY;
+                self.orbit.pan(-dx * k, dy * k);
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn mouse_wheel_event(&mut self, _ctx: &mut Context, _x: f32, y: f32) -> GameResult {
+        if self.mode == ViewMode::Orbit {
+            self.orbit.zoom(ZOOM_STEP.powf(y));
+            self.update_projection();
+        }
+        Ok(())
+    }
+
+    fn resize_event(&mut self, _ctx: &mut Context, width: f32, height: f32) -> GameResult {
+        self.width = width.max(1.0);
+        self.height = height.max(1.0);
+        self.framebuffer = Framebuffer::new(self.width as usize, self.height as usize);
+        self.update_projection();
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let (world, camera, forward): (Mat4x4, &dyn Viewpoint, Vec3d) = match self.mode {
+            ViewMode::Spin => {
+                let rot_z = Quat::from_axis_angle(&Vec3d::unit_z(), self.theta);
+                let rot_x = Quat::from_axis_angle(&Vec3d::unit_x(), self.theta * 0.5);
+                let rot = Quat::mult(&rot_z, &rot_x).to_mat4x4();
+                (rot * Mat4x4::translation(0.0, 0.0, SPIN_DISTANCE), &self.camera, self.camera.forward())
+            }
+            ViewMode::Orbit => (Mat4x4::identity(), &self.orbit, self.orbit.forward()),
+        };
+        let mut lights = self.lights.clone();
+        if self.headlight {
+            lights.push(Light::directional(&forward));
+        }
+        let mut pipeline = Pipeline::new(world, camera, lights, self.width, self.height);
+        pipeline.proj = self.proj;
+        pipeline.shading = self.shading;
+        pipeline.lighting.reflection = self.reflection;
+        pipeline.filter = self.filter;
+        let mut to_draw = pipeline.process_scene(&mut self.scene);
+
+        let mut canvas = graphics::Canvas::from_frame(ctx, Color::BLACK);
+        canvas.set_screen_coordinates(graphics::Rect::new(0.0, 0.0, self.width, self.height));
+
+        match self.render_mode {
+            RenderMode::Painter => {
+                // Draw polygons back to front, each covering those behind it
+                let mb = &mut graphics::MeshBuilder::new();
+                for polygon in raster::painter(to_draw) {
+                    let points = polygon.points.map(|(x, y)| vec2(x, y));
+                    if let Some(rgb) = &polygon.fill {
+                        mb.polygon(graphics::DrawMode::fill(), &points, to_color(rgb))?;
+                    }
+                    if let Some(rgb) = &polygon.stroke {
+                        mb.polygon(graphics::DrawMode::stroke(2.0), &points, to_color(rgb))?;
+                    }
+                }
+
+                let mesh = graphics::Mesh::from_data(ctx, mb.build());
+                canvas.draw(&mesh, graphics::DrawParam::default());
+            }
+            RenderMode::DepthBuffer => {
+                // Rasterize every triangle, keeping the closest at each pixel
+                self.framebuffer.clear([0, 0, 0, 255]);
+                for tri in &mut to_draw {
+                    self.framebuffer.draw_triangle(tri, &pipeline.lighting);
+                }
+
+                let image = graphics::Image::from_pixels(
+                    ctx,
+                    self.framebuffer.pixels(),
+                    graphics::ImageFormat::Rgba8UnormSrgb,
+                    self.framebuffer.width() as u32,
+                    self.framebuffer.height() as u32,
+                );
+                canvas.draw(&image, graphics::DrawParam::default());
+            }
+        }
+
+        canvas.finish(ctx)?;
+
+        Ok(())
+    }
+}
+
@@ -0,0 +1,234 @@
+//! Golden-image tests: render meshes headlessly and compare against reference images in `tests/golden`.
+//!
+//! Run with `UPDATE_GOLDEN=1` to (re)write the reference images after an intentional change to the output.
+//! When a comparison fails, the rendered frame and a diff image are written to `target/golden`.
+
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use colorsys::Rgb;
+
+use threed_renderer::lighting::Light;
+use threed_renderer::mesh::{obj, FaceSettings};
+use threed_renderer::pipeline;
+use threed_renderer::texture::{Filter, Texture};
+use threed_renderer::{shape, Framebuffer, Mat4x4, Mesh, Node, OrbitCamera, Pipeline, Quat, Scene, Shading, Transform, Vec3d};
+
+const WIDTH: usize = 160;
+const HEIGHT: usize = 120;
+/// Largest difference allowed in any channel of a pixel before it counts as mismatched
+const TOLERANCE: u8 = 3;
+/// Fraction of pixels which may mismatch, to absorb floating point differences along triangle edges
+const MAX_MISMATCHED: f32 = 0.001;
+
+fn reference_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden")
+}
+
+fn output_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target").join("golden")
+}
+
+/// Construct a camera fitted to `mesh`, then orbited by `yaw` and `pitch` degrees
+fn fitted_camera(mesh: &Mesh, yaw: f32, pitch: f32) -> OrbitCamera {
+    let mut camera = OrbitCamera::new(Vec3d::origin(), 5.0);
+    let (min, max) = mesh.bounds().unwrap();
+    camera.fit(&min, &max, pipeline::FOV.to_radians());
+    camera.orbit(yaw.to_radians(), pitch.to_radians());
+    camera
+}
+
+/// Construct a pipeline looking through `fitted_camera`
+fn fitted(mesh: &Mesh, yaw: f32, pitch: f32, lights: Vec<Light>) -> Pipeline {
+    Pipeline::new(Mat4x4::identity(), &fitted_camera(mesh, yaw, pitch), lights, WIDTH as f32, HEIGHT as f32)
+}
+
+/// Render `mesh` with the camera fitted to it, then orbited by `yaw` and `pitch` degrees
+fn render_lit(mesh: &mut Mesh, yaw: f32, pitch: f32, shading: Shading, lights: Vec<Light>) -> Framebuffer {
+    let mut pipeline = fitted(mesh, yaw, pitch, lights);
+    pipeline.shading = shading;
+    pipeline.render(mesh)
+}
+
+/// Render `mesh` as `render_lit`, lit by a white light travelling away from the initial camera position
+fn render(mesh: &mut Mesh, yaw: f32, pitch: f32, shading: Shading) -> Framebuffer {
+    render_lit(mesh, yaw, pitch, shading, vec![Light::directional(&Vec3d::unit_z())])
+}
+
+/// Read an RGBA PNG, returning its size and pixels
+fn read_png(path: &PathBuf) -> (usize, usize, Vec<u8>) {
+    let file = File::open(path).unwrap_or_else(|e| panic!("Could not open {} ({}). Run with UPDATE_GOLDEN=1 to create it", path.display(), e));
+    let mut reader = png::Decoder::new(BufReader::new(file)).read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    assert_eq!(info.color_type, png::ColorType::Rgba, "{} is not RGBA", path.display());
+    pixels.truncate(info.buffer_size());
+    (info.width as usize, info.height as usize, pixels)
+}
+
+/// Write RGBA pixels as a PNG
+fn write_png(path: &PathBuf, width: usize, height: usize, pixels: &[u8]) {
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path).unwrap()), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header().unwrap().write_image_data(pixels).unwrap();
+}
+
+/// Compare a frame against the reference image `name`, writing the frame and a diff image on failure
+fn check(name: &str, frame: &Framebuffer) {
+    let reference = reference_dir().join(format!("{}.png", name));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(reference_dir()).unwrap();
+        frame.save(&reference).unwrap();
+        return;
+    }
+
+    let (width, height, expected) = read_png(&reference);
+    assert_eq!((width, height), (frame.width(), frame.height()), "{}: size differs from reference", name);
+
+    // Diff image: mismatched pixels in red, everything else a faded copy of the reference
+    let mut diff = Vec::with_capacity(expected.len());
+    let mut mismatched = 0;
+    for (a, b) in frame.pixels().chunks_exact(4).zip(expected.chunks_exact(4)) {
+        if a.iter().zip(b).any(|(x, y)| x.abs_diff(*y) > TOLERANCE) {
+            mismatched += 1;
+            diff.extend([255, 0, 0, 255]);
+        } else {
+            diff.extend([b[0] / 4, b[1] / 4, b[2] / 4, 255]);
+        }
+    }
+
+    if mismatched as f32 > MAX_MISMATCHED * (width * height) as f32 {
+        fs::create_dir_all(output_dir()).unwrap();
+        let actual = output_dir().join(format!("{}.actual.png", name));
+        let diff_path = output_dir().join(format!("{}.diff.png", name));
+        frame.save(&actual).unwrap();
+        write_png(&diff_path, width, height, &diff);
+        panic!(
+            "{}: {} of {} pixels differ from {}. See {} and {}",
+            name, mismatched, width * height, reference.display(), actual.display(), diff_path.display()
+        );
+    }
+}
+
+#[test]
+fn cube() {
+    check("cube", &render(&mut shape::cube(1.0), 30.0, -25.0, Shading::Flat));
+}
+
+#[test]
+fn sphere() {
+    check("sphere", &render(&mut shape::sphere(1.0, 16, 12), 0.0, -30.0, Shading::Flat));
+}
+
+#[test]
+fn tetrahedron() {
+    check("tetrahedron", &render(&mut shape::tetrahedron(&Vec3d::diag(1.0)), 45.0, -20.0, Shading::Flat));
+}
+
+#[test]
+fn spaceship() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("models").join("spaceship.obj");
+    let mut mesh = obj::parse_file(path.to_string_lossy().to_string()).unwrap();
+    check("spaceship", &render(&mut mesh, 30.0, -20.0, Shading::Flat));
+}
+
+#[test]
+fn sphere_smooth() {
+    let mut mesh = shape::sphere(1.0, 16, 12);
+    mesh.default_stroke = None;
+    check("sphere_smooth", &render(&mut mesh, 0.0, -30.0, Shading::Smooth));
+}
+
+#[test]
+fn sphere_specular() {
+    let mut mesh = shape::sphere(1.0, 16, 12);
+    let style = mesh.add_style(&FaceSettings {
+        fill: Some(Rgb::new(64.0, 96.0, 192.0, None)),
+        specular: Some(Rgb::new(255.0, 255.0, 255.0, None)),
+        shininess: 32.0,
+        ..Default::default()
+    });
+    mesh.set_global_style(Some(style));
+    check("sphere_specular", &render(&mut mesh, 20.0, -30.0, Shading::PerPixel));
+}
+
+#[test]
+fn sphere_lights() {
+    let mut mesh = shape::sphere(1.0, 16, 12);
+    mesh.default_stroke = None;
+    let lights = vec![
+        Light::Point { position: Vec3d::new(-2.0, 1.0, -2.0), color: Rgb::new(255.0, 64.0, 64.0, None), intensity: 2.0, attenuation: (1.0, 0.0, 0.1) },
+        Light::Spot {
+            position: Vec3d::new(2.0, 2.0, -3.0),
+            direction: Vec3d::new(-2.0, -2.0, 3.0),
+            color: Rgb::new(64.0, 64.0, 255.0, None),
+            intensity: 1.0,
+            attenuation: (1.0, 0.0, 0.0),
+            inner: 10.0f32.to_radians(),
+            outer: 20.0f32.to_radians(),
+        },
+    ];
+    check("sphere_lights", &render_lit(&mut mesh, 0.0, -30.0, Shading::PerPixel, lights));
+}
+
+/// A floor tiled with a 4x4 checkerboard, repeated twice in each direction, seen at a low angle so perspective is strong
+fn checkerboard(filter: Filter) -> Framebuffer {
+    let texels = (0..16).map(|i| if (i % 4 + i / 4) % 2 == 0 { [230, 230, 230] } else { [200, 40, 40] }).collect();
+    let mut mesh = Mesh::new();
+    let style = mesh.add_style(&FaceSettings {
+        image: Some(Rc::new(Texture::new(4, 4, texels).unwrap())),
+        ..Default::default()
+    });
+    let corners = [(-1.0, -1.0), (-1.0, 1.0), (1.0, 1.0), (1.0, -1.0)];
+    for (x, z) in corners {
+        let i = mesh.add_vertex_force(&Vec3d::new(x, 0.0, z));
+        mesh.set_vertex_uv(i, Some((x + 1.0, z + 1.0)));
+    }
+    mesh.add_quad(0, 1, 2, 3, &Some(style));
+
+    let mut pipeline = fitted(&mesh, 20.0, -20.0, vec![Light::directional(&Vec3d::new(0.0, -1.0, 0.0))]);
+    pipeline.filter = filter;
+    pipeline.render(&mut mesh)
+}
+
+#[test]
+fn checkerboard_nearest() {
+    check("checkerboard_nearest", &checkerboard(Filter::Nearest));
+}
+
+#[test]
+fn checkerboard_bilinear() {
+    check("checkerboard_bilinear", &checkerboard(Filter::Bilinear));
+}
+
+#[test]
+fn cube_orthographic() {
+    let mut mesh = shape::cube(1.0);
+    let camera = fitted_camera(&mesh, 30.0, -25.0);
+    let mut pipeline = Pipeline::new(Mat4x4::identity(), &camera, vec![Light::directional(&Vec3d::unit_z())], WIDTH as f32, HEIGHT as f32);
+    pipeline.proj = pipeline::orthographic(camera.distance, WIDTH as f32, HEIGHT as f32);
+    check("cube_orthographic", &pipeline.render(&mut mesh));
+}
+
+#[test]
+fn scene_instances() {
+    // One cube, drawn by a row of nodes under a turned parent, each scaled and lifted a little more than the last
+    let mut scene = Scene::new();
+    let cube = scene.add_mesh(shape::cube(1.0));
+    let row = scene.add_node(Node::new(Transform { rotation: Quat::from_axis_angle(&Vec3d::unit_y(), 0.5), ..Default::default() }, None), None);
+    for i in 0..3 {
+        let k = i as f32;
+        let transform = Transform { translation: Vec3d::new(1.5 * k - 1.5, 0.3 * k, 0.0), scale: Vec3d::diag(0.5 + 0.25 * k), ..Default::default() };
+        scene.add_node(Node::new(transform, Some(cube)), Some(row));
+    }
+
+    let mut camera = OrbitCamera::new(Vec3d::origin(), 5.0);
+    let (min, max) = scene.bounds().unwrap();
+    camera.fit(&min, &max, pipeline::FOV.to_radians());
+    camera.orbit(0.0, (-20.0f32).to_radians());
+    let pipeline = Pipeline::new(Mat4x4::identity(), &camera, vec![Light::directional(&Vec3d::new(0.3, -0.5, 1.0))], WIDTH as f32, HEIGHT as f32);
+    check("scene_instances", &pipeline.render_scene(&mut scene));
+}